@@ -0,0 +1,139 @@
+// compare the sprite and chunk mesh renderer backends for a big map (100k tiles)
+//
+// Besides setting things up, this measures the per frame cost on the main world side (transform propagation
+// and visibility checks). Extraction into the render world and the GPU side need a window and are not covered,
+// the visible count shows how many sprites / meshes would be extracted and batched each frame.
+//
+// run with: cargo bench --bench chunk_mesh
+use std::time::{Duration, Instant};
+
+use bevy::{
+    asset::AssetPlugin, prelude::*, render::view::VisibilityPlugin, sprite::Mesh2dHandle,
+    transform::TransformPlugin,
+};
+use game1::hex::{
+    chunk::chunk_cubes, chunk_mesh::build_chunk_mesh, tilemap::HexTileAppearance,
+    tilemap::HexTileCoord, Cube,
+};
+
+const CHUNKS: i32 = 20; // 20 x 20 chunks of 16 x 16 = 102400 tiles
+const FRAMES: u32 = 20;
+
+// the same tile hierarchy as in the game (base -> chunks -> tiles), tiles with a sprite or chunks with a mesh
+fn build_app(chunks: &[Vec<(Cube, usize)>], tile_size: Vec2, sprites: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin)
+        .add_asset::<Mesh>()
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(VisibilityPlugin);
+
+    let world = &mut app.world;
+    world.spawn().insert_bundle(Camera2dBundle::default());
+    let base = world.spawn().insert_bundle(SpatialBundle::default()).id();
+    for tiles in chunks {
+        let chunk = world.spawn().insert_bundle(SpatialBundle::default()).id();
+        if !sprites {
+            world.entity_mut(chunk).insert(Mesh2dHandle::default());
+        }
+        world.entity_mut(base).push_children(&[chunk]);
+        for (cube, tile_type) in tiles {
+            let mut tile = world.spawn();
+            tile.insert_bundle(SpatialBundle::from_transform(Transform::from_translation(
+                (cube.to_odd_r_screen() * tile_size).extend(0.0),
+            )))
+            .insert(HexTileCoord { cube: *cube })
+            .insert(HexTileAppearance {
+                tile_type: *tile_type,
+            });
+            if sprites {
+                tile.insert(TextureAtlasSprite {
+                    index: *tile_type,
+                    ..Default::default()
+                })
+                .insert(Handle::<TextureAtlas>::default());
+            }
+            let tile = tile.id();
+            world.entity_mut(chunk).push_children(&[tile]);
+        }
+    }
+    app
+}
+
+// average frame time and number of visible sprites / meshes (the first frame touches everything once and is skipped)
+fn frame_time(app: &mut App) -> (Duration, usize) {
+    app.update();
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        app.update();
+    }
+    let elapsed = start.elapsed() / FRAMES;
+    // (tiles without a sprite are visible as well, but there is nothing to extract for them)
+    let visible = app
+        .world
+        .query_filtered::<&ComputedVisibility, Or<(With<TextureAtlasSprite>, With<Mesh2dHandle>)>>()
+        .iter(&app.world)
+        .filter(|visibility| visibility.is_visible())
+        .count();
+    (elapsed, visible)
+}
+
+fn main() {
+    let tile_size = Vec2::new(18.0, 20.0);
+    let atlas = TextureAtlas::from_grid(Handle::default(), tile_size, 7, 1);
+
+    let chunks = (0..CHUNKS)
+        .flat_map(|y| (0..CHUNKS).map(move |x| IVec2::new(x, y)))
+        .map(|chunk| {
+            chunk_cubes(chunk)
+                .into_iter()
+                .enumerate()
+                .map(|(i, cube)| (cube, i % 7))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let num_tiles = chunks.iter().map(Vec::len).sum::<usize>();
+
+    // sprite backend: one sprite per tile
+    let start = Instant::now();
+    let mut app = build_app(&chunks, tile_size, true);
+    println!(
+        "sprites: spawned {} tile entities in {:?}",
+        num_tiles,
+        start.elapsed()
+    );
+    let (elapsed, visible) = frame_time(&mut app);
+    println!(
+        "sprites: {:?} per frame for transforms and visibility, {} visible sprites / meshes to extract",
+        elapsed, visible
+    );
+
+    // chunk mesh backend: one mesh per chunk, only rebuilt on changes
+    let start = Instant::now();
+    let mut vertices = 0;
+    for tiles in chunks.iter() {
+        let mesh = build_chunk_mesh(tiles.iter().copied(), tile_size, &atlas);
+        vertices += mesh.count_vertices();
+    }
+    println!(
+        "chunk mesh: built {} meshes ({} vertices) in {:?}",
+        chunks.len(),
+        vertices,
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    build_chunk_mesh(chunks[0].iter().copied(), tile_size, &atlas);
+    println!(
+        "chunk mesh: rebuilt a single chunk (editing a tile) in {:?}",
+        start.elapsed()
+    );
+
+    let mut app = build_app(&chunks, tile_size, false);
+    let (elapsed, visible) = frame_time(&mut app);
+    println!(
+        "chunk mesh: {:?} per frame for transforms and visibility, {} visible sprites / meshes to extract",
+        elapsed, visible
+    );
+}
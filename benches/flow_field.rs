@@ -0,0 +1,79 @@
+// crowds chasing one target: an A* search per agent vs. one flow field that all agents sample
+//
+// run with: cargo bench --bench flow_field
+use std::{collections::HashMap, time::Instant};
+
+use bevy::prelude::*;
+use game1::{
+    hex::Cube,
+    path::{flow_field::FlowField, grid::find_path},
+};
+
+const MAP_SIZES: [i32; 3] = [32, 64, 128];
+const AGENTS: usize = 100;
+
+fn main() {
+    for size in MAP_SIZES {
+        let mut costs = HashMap::new();
+        for y in 0..size {
+            for x in 0..size {
+                // walls to go around, some water to avoid
+                let cost = match (x * 7 + y * 3) % 11 {
+                    0 | 1 => continue,
+                    2 => 2.0,
+                    _ => 1.0,
+                };
+                costs.insert(Cube::from_odd_r(Vec2::new(x as f32, y as f32)), cost);
+            }
+        }
+        let cost = |cube: Cube| costs.get(&cube).copied();
+        let walkable = |x: i32, y: i32| {
+            let cube = Cube::from_odd_r(Vec2::new(x as f32, y as f32));
+            costs.contains_key(&cube).then_some(cube)
+        };
+        let goal = (0..size)
+            .find_map(|x| walkable(x + size / 2, size / 2))
+            .unwrap();
+        // agents spread over the whole map
+        let agents = (0..)
+            .filter_map(|i| walkable((i * 31) % size, (i * 17 + i / size) % size))
+            .take(AGENTS)
+            .collect::<Vec<_>>();
+
+        let start = Instant::now();
+        let mut total = 0.0;
+        for agent in agents.iter() {
+            if let Some((_, cost)) = find_path(*agent, goal, cost) {
+                total += cost;
+            }
+        }
+        println!(
+            "{}x{} tiles: A* for {} agents in {:?} (total cost {})",
+            size,
+            size,
+            agents.len(),
+            start.elapsed(),
+            total
+        );
+
+        let start = Instant::now();
+        let field = FlowField::new(goal, cost).unwrap();
+        let built = start.elapsed();
+        let mut total = 0.0;
+        for agent in agents.iter() {
+            // the agents only need field.next, but walking the whole way shows the paths are as cheap
+            if field.path(*agent).is_some() {
+                total += field.cost(*agent).unwrap();
+            }
+        }
+        println!(
+            "{}x{} tiles: flow field for {} agents in {:?} (build {:?}, total cost {})",
+            size,
+            size,
+            agents.len(),
+            start.elapsed(),
+            built,
+            total
+        );
+    }
+}
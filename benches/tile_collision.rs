@@ -0,0 +1,91 @@
+// cost of moving against the tilemap for growing map sizes: scanning all tiles for every mover (the old
+// approach) vs. looking up only the hexes around the mover in the tile index
+//
+// run with: cargo bench --bench tile_collision
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use bevy::prelude::*;
+use game1::hex::{collision::slide_circle, tilemap::HexTileAppearance, Cube};
+
+const MAP_SIZES: [i32; 4] = [16, 64, 256, 512];
+const MOVERS: usize = 1000;
+// the scan gets too slow for the big maps
+const MAX_SCAN_SIZE: i32 = 64;
+
+fn main() {
+    let tile_size = Vec2::new(18.0, 20.0);
+    let radius = 3.0;
+    let delta = Vec2::new(0.3, 0.2);
+
+    for size in MAP_SIZES {
+        let mut world = World::new();
+        let mut index = HashMap::new();
+        for y in 0..size {
+            for x in 0..size {
+                let cube = Cube::from_odd_r(Vec2::new(x as f32, y as f32));
+                // some walls to collide with
+                let tile_type = if (x * 7 + y * 3) % 5 == 0 { 0 } else { 2 };
+                let entity = world.spawn().insert(HexTileAppearance { tile_type }).id();
+                index.insert(cube, entity);
+            }
+        }
+        // movers spread over the whole map
+        let movers = (0..MOVERS)
+            .map(|i| {
+                let x = (i as i32 * 31) % size;
+                let y = (i as i32 * 17) % size;
+                Cube::from_odd_r(Vec2::new(x as f32, y as f32)).to_odd_r_screen() * tile_size
+            })
+            .collect::<Vec<_>>();
+
+        let is_solid = |cube: Cube| {
+            index
+                .get(&cube)
+                .and_then(|entity| world.get::<HexTileAppearance>(*entity))
+                .is_some_and(|appearance| appearance.tile_type == 0)
+        };
+        let start = Instant::now();
+        let mut moved = Vec2::ZERO;
+        for pos in movers.iter() {
+            moved += slide_circle(*pos, delta, radius, tile_size, is_solid);
+        }
+        println!(
+            "{}x{} tiles: index lookup for {} movers in {:?} ({:?})",
+            size,
+            size,
+            MOVERS,
+            start.elapsed(),
+            moved
+        );
+
+        if size > MAX_SCAN_SIZE {
+            continue;
+        }
+        let mut query = world.query::<(Entity, &HexTileAppearance)>();
+        let cubes = index
+            .iter()
+            .map(|(cube, entity)| (*entity, *cube))
+            .collect::<HashMap<_, _>>();
+        let start = Instant::now();
+        let mut moved = Vec2::ZERO;
+        for pos in movers.iter() {
+            let solid = query
+                .iter(&world)
+                .filter(|(_, appearance)| appearance.tile_type == 0)
+                .map(|(entity, _)| cubes[&entity])
+                .collect::<HashSet<_>>();
+            moved += slide_circle(*pos, delta, radius, tile_size, |cube| solid.contains(&cube));
+        }
+        println!(
+            "{}x{} tiles: scan of all tiles for {} movers in {:?} ({:?})",
+            size,
+            size,
+            MOVERS,
+            start.elapsed(),
+            moved
+        );
+    }
+}
@@ -4,6 +4,8 @@
 pub mod anim;
 mod loader;
 
+use std::time::Duration;
+
 use anim::AsepriteAnimation;
 use bevy::prelude::*;
 use bevy::reflect::TypeUuid;
@@ -44,6 +46,48 @@ pub struct Aseprite {
     atlas: Option<Handle<TextureAtlas>>,
 }
 
+impl Aseprite {
+    /// The atlas containing all frames, available once the file has been processed
+    pub fn atlas(&self) -> Option<&Handle<TextureAtlas>> {
+        self.atlas.as_ref()
+    }
+
+    /// The atlas index of a frame
+    pub fn atlas_index(&self, frame: usize) -> Option<usize> {
+        self.frame_to_idx.get(frame).copied()
+    }
+
+    /// The frames of a tag in playback order together with their durations.
+    /// Ping-pong tags are unrolled into one back-and-forth cycle.
+    pub fn tag_frames(&self, tag: &str) -> Option<Vec<(usize, Duration)>> {
+        use reader::raw::AsepriteAnimationDirection;
+
+        let info = self.info.as_ref()?;
+        let tag = info.tags.get(tag)?;
+        let range = tag.frames.start as usize..tag.frames.end as usize;
+        let frames: Vec<usize> = match tag.animation_direction {
+            AsepriteAnimationDirection::Forward => range.collect(),
+            AsepriteAnimationDirection::Reverse => range.rev().collect(),
+            AsepriteAnimationDirection::PingPong => {
+                let inner = range.len().saturating_sub(2);
+                range
+                    .clone()
+                    .chain(range.rev().skip(1).take(inner))
+                    .collect()
+            }
+        };
+        Some(
+            frames
+                .into_iter()
+                .map(|frame| {
+                    let delay_ms = info.frame_infos[frame].delay_ms as u64;
+                    (frame, Duration::from_millis(delay_ms))
+                })
+                .collect(),
+        )
+    }
+}
+
 /// A bundle defining a drawn aseprite
 #[derive(Debug, Bundle, Default)]
 pub struct AsepriteBundle {
@@ -52,3 +96,72 @@ pub struct AsepriteBundle {
     pub animation: AsepriteAnimation,
     pub aseprite: Handle<Aseprite>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use reader::{raw::AsepriteAnimationDirection, AsepriteFrameInfo, AsepriteTag};
+
+    // five frames (frame n lasts (n + 1) * 10ms) and a tag "tag" over frames 1 to 3
+    fn aseprite(animation_direction: AsepriteAnimationDirection) -> Aseprite {
+        let tag = AsepriteTag {
+            frames: 1..4,
+            animation_direction,
+            name: "tag".to_string(),
+        };
+        Aseprite {
+            data: None,
+            info: Some(AsepriteInfo {
+                dimensions: (1, 1),
+                tags: HashMap::from([(tag.name.clone(), tag)]),
+                slices: HashMap::new(),
+                frame_count: 5,
+                palette: None,
+                transparent_palette: None,
+                frame_infos: (0..5)
+                    .map(|frame| AsepriteFrameInfo {
+                        delay_ms: (frame + 1) * 10,
+                    })
+                    .collect(),
+            }),
+            frame_to_idx: vec![],
+            atlas: None,
+        }
+    }
+
+    fn frames(animation_direction: AsepriteAnimationDirection) -> Vec<usize> {
+        aseprite(animation_direction)
+            .tag_frames("tag")
+            .unwrap()
+            .into_iter()
+            .map(|(frame, _)| frame)
+            .collect()
+    }
+
+    #[test]
+    fn tag_frames_order() {
+        assert_eq!(frames(AsepriteAnimationDirection::Forward), [1, 2, 3]);
+        assert_eq!(frames(AsepriteAnimationDirection::Reverse), [3, 2, 1]);
+        assert_eq!(frames(AsepriteAnimationDirection::PingPong), [1, 2, 3, 2]);
+    }
+
+    #[test]
+    fn tag_frames_durations() {
+        let frames = aseprite(AsepriteAnimationDirection::Forward)
+            .tag_frames("tag")
+            .unwrap();
+        assert_eq!(
+            frames,
+            [
+                (1, Duration::from_millis(20)),
+                (2, Duration::from_millis(30)),
+                (3, Duration::from_millis(40)),
+            ]
+        );
+        assert!(aseprite(AsepriteAnimationDirection::Forward)
+            .tag_frames("missing")
+            .is_none());
+    }
+}
@@ -3,10 +3,8 @@ use big_brain::prelude::*;
 
 use crate::{
     ai::util::TargetDistanceProbe,
-    movement::{
-        control::MovementGoToPoint, crab_controller::CrabFollowPath, crab_move::CrabMoveWalker,
-    },
-    TargetFlag,
+    movement::{control::MovementFollowTarget, crab_move::CrabMoveWalker},
+    path::PathStatus,
 };
 
 use super::DebugAction;
@@ -19,19 +17,13 @@ pub struct Follow {
 pub fn follow_action_system(
     mut commands: Commands,
     mut walkers: Query<(&Transform, &TargetDistanceProbe, &mut CrabMoveWalker)>,
-    target_query: Query<&Transform, With<TargetFlag>>,
     // We execute actions by querying for their associated Action Component
     // (Drink in this case). You'll always need both Actor and ActionState.
     mut query: Query<(&Actor, &mut ActionState, &Follow)>,
-    go_to_point_query: Query<(), With<MovementGoToPoint>>,
+    follow_target_query: Query<(), With<MovementFollowTarget>>,
+    status_query: Query<&PathStatus>,
 ) {
-    let target_pos = target_query
-        .iter()
-        .next()
-        .map(|t| t.translation)
-        .unwrap_or_default();
-
-    for (Actor(actor), mut state, _go_to_target) in query.iter_mut() {
+    for (Actor(actor), mut state, follow) in query.iter_mut() {
         commands
             .entity(*actor)
             .insert(DebugAction::new("follow", state.clone()));
@@ -43,23 +35,25 @@ pub fn follow_action_system(
                     // println!("Time to follow the target!");
                     // let tv = (target_pos - transform.translation).normalize();
                     // walker.velocity = -0.5 * tv;
-                    commands
-                        .entity(*actor)
-                        .insert(MovementGoToPoint(target_pos));
+                    // a target found unreachable stays so until the flow field changes (see
+                    // crab_follow_target_system), no need to look again
+                    commands.entity(*actor).insert(MovementFollowTarget {
+                        until: follow.until,
+                    });
                     *state = ActionState::Executing;
                 }
-                ActionState::Executing => {
-                    if go_to_point_query.get(*actor).is_err() {
-                        *state = ActionState::Success;
-                    }
+                ActionState::Executing if status_query.get(*actor) == Ok(&PathStatus::Failed) => {
+                    // info!("no way to the target");
+                    commands.entity(*actor).remove::<MovementFollowTarget>();
+                    *state = ActionState::Failure;
+                }
+                ActionState::Executing if follow_target_query.get(*actor).is_err() => {
+                    *state = ActionState::Success;
                 }
                 // All Actions should make sure to handle cancellations!
                 ActionState::Cancelled => {
                     // info!("follow target cancelled");
-                    commands
-                        .entity(*actor)
-                        .remove::<MovementGoToPoint>()
-                        .remove::<CrabFollowPath>();
+                    commands.entity(*actor).remove::<MovementFollowTarget>();
 
                     *state = ActionState::Failure;
                 }
@@ -2,8 +2,9 @@ use bevy::prelude::*;
 use big_brain::prelude::*;
 
 use crate::{
-    item::medikit::Medikit,
+    item::medikit::NearestMedikit,
     movement::{control::MovementGoToPoint, crab_controller::CrabFollowPath},
+    path::PathStatus,
 };
 
 use super::DebugAction;
@@ -16,31 +17,23 @@ pub struct GotoMedikit {
 pub fn goto_medikit_action_system(
     mut commands: Commands,
     mut query: Query<(&Actor, &mut ActionState, &mut GotoMedikit)>,
-    actor_query: Query<&Transform>,
-    medikit_query: Query<&Transform, With<Medikit>>,
+    nearest_query: Query<&NearestMedikit>,
     go_to_point_query: Query<(), With<MovementGoToPoint>>,
+    status_query: Query<&PathStatus>,
 ) {
     for (Actor(actor), mut state, mut goto_medikit) in query.iter_mut() {
         commands
             .entity(*actor)
             .insert(DebugAction::new("goto medikit", state.clone()));
 
-        let Transform {
-            translation: actor_pos,
-            ..
-        } = actor_query.get(*actor).unwrap();
         match *state {
             // let mut nearest_dist
             ActionState::Init => {
-                let best_pos = medikit_query
-                    .iter()
-                    .map(
-                        |Transform {
-                             translation: medikit_pos,
-                             ..
-                         }| *medikit_pos,
-                    )
-                    .min_by_key(|medikit_pos| (*medikit_pos - *actor_pos).length() as i32);
+                // see nearest_medikit_system
+                let best_pos = nearest_query
+                    .get(*actor)
+                    .ok()
+                    .and_then(|nearest| nearest.pos);
 
                 if let Some(best_pos) = best_pos {
                     goto_medikit.pos = best_pos;
@@ -49,19 +42,29 @@ pub fn goto_medikit_action_system(
                     //     start: *actor_pos,
                     //     end: best_pos,
                     // });
-                    commands.entity(*actor).insert(MovementGoToPoint(best_pos));
+                    // forget about earlier failures
+                    commands
+                        .entity(*actor)
+                        .insert(MovementGoToPoint(best_pos))
+                        .remove::<PathStatus>();
                     *state = ActionState::Executing
                 } else {
-                    info!("failed to find medikit");
+                    info!("failed to find medikit within reach");
                     *state = ActionState::Failure
                 }
             }
-            ActionState::Executing => {
-                // let tv = (goto_medikit.pos - *actor_pos).normalize();
-                // walker.direction = CrabMoveDirection::find_nearest(tv);
-                if go_to_point_query.get(*actor).is_err() {
-                    *state = ActionState::Success;
-                }
+            // let tv = (goto_medikit.pos - *actor_pos).normalize();
+            // walker.direction = CrabMoveDirection::find_nearest(tv);
+            ActionState::Executing if status_query.get(*actor) == Ok(&PathStatus::Failed) => {
+                info!("no way to medikit");
+                commands
+                    .entity(*actor)
+                    .remove::<MovementGoToPoint>()
+                    .remove::<CrabFollowPath>();
+                *state = ActionState::Failure;
+            }
+            ActionState::Executing if go_to_point_query.get(*actor).is_err() => {
+                *state = ActionState::Success;
             }
             ActionState::Cancelled => {
                 commands
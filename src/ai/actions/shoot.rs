@@ -5,7 +5,7 @@ use big_brain::prelude::*;
 use crate::{
     ai::util::Ammo,
     movement::crab_move::{CrabMoveDirection, CrabMoveWalker},
-    sprites, tune, Despawn, Pew, TargetFlag,
+    sprites, tune, Despawn, Pew, PewPrevPosition, TargetFlag,
 };
 
 use super::DebugAction;
@@ -75,6 +75,7 @@ pub fn shoot_action_system(
                             ..Default::default()
                         })
                         .insert(Pew(shoot.shoot_right, 15.0))
+                        .insert(PewPrevPosition(*translation + offset))
                         .insert(Despawn::TimeToLive(10.0));
                     shoot.reload = 0.2;
                     ammo.ammo -= 1.0;
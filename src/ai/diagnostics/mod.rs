@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use big_brain::prelude::*;
 
 #[derive(Default)]
+#[allow(dead_code)]
 pub struct DiagnosticsTarget(Vec<(Entity, Entity)>);
 
 // mixed experiments for extracting diagnostic info from big_brain Actors (strictly for debug visualization etc.)
@@ -1,7 +1,10 @@
 use bevy::prelude::*;
 use big_brain::BigBrainStage;
 
-use crate::ai::{inspect::AiInspectState, util::ammo_reload_system};
+use crate::{
+    ai::{inspect::AiInspectState, util::ammo_reload_system},
+    state::AppState,
+};
 
 pub mod actions;
 pub mod diagnostics;
@@ -35,24 +38,33 @@ impl Plugin for AiPlugin {
 
         app.register_type::<util::TargetDistanceProbe>()
             .register_type::<HealthPoints>()
-            .add_system(inspect::ai_inspect_egui_system)
-            .add_system(inspect::ai_inspect_pick_target)
             .init_resource::<AiInspectState>()
             .add_system_to_stage(CoreStage::PostUpdate, util::measure_target_distance_system)
-            .add_system(ammo_reload_system)
-            // actions
-            .add_system_to_stage(BigBrainStage::Actions, run_away_action_system)
-            .add_system_to_stage(BigBrainStage::Actions, follow_action_system)
-            .add_system_to_stage(BigBrainStage::Actions, jiggle_around_action_system)
-            .add_system_to_stage(BigBrainStage::Actions, dodge_pew_action_system)
-            .add_system_to_stage(BigBrainStage::Actions, goto_medikit_action_system)
-            .add_system_to_stage(BigBrainStage::Actions, shoot_action_system)
-            // scorers
-            .add_system_to_stage(BigBrainStage::Scorers, fear_scorer_system)
-            .add_system_to_stage(BigBrainStage::Scorers, curiousity_scorer_system)
-            .add_system_to_stage(BigBrainStage::Scorers, pew_incoming_scorer_system)
-            .add_system_to_stage(BigBrainStage::Scorers, low_health_scorer_system)
-            .add_system_to_stage(BigBrainStage::Scorers, can_shoot_scorer_system)
-            .add_system_to_stage(BigBrainStage::Scorers, crowdiness_scorer_system);
+            .add_system_set(
+                SystemSet::on_update(AppState::Play)
+                    .with_system(inspect::ai_inspect_egui_system)
+                    .with_system(inspect::ai_inspect_pick_target)
+                    .with_system(ammo_reload_system),
+            )
+            .add_system_set_to_stage(
+                BigBrainStage::Actions,
+                SystemSet::on_update(AppState::Play)
+                    .with_system(run_away_action_system)
+                    .with_system(follow_action_system)
+                    .with_system(jiggle_around_action_system)
+                    .with_system(dodge_pew_action_system)
+                    .with_system(goto_medikit_action_system)
+                    .with_system(shoot_action_system),
+            )
+            .add_system_set_to_stage(
+                BigBrainStage::Scorers,
+                SystemSet::on_update(AppState::Play)
+                    .with_system(fear_scorer_system)
+                    .with_system(curiousity_scorer_system)
+                    .with_system(pew_incoming_scorer_system)
+                    .with_system(low_health_scorer_system)
+                    .with_system(can_shoot_scorer_system)
+                    .with_system(crowdiness_scorer_system),
+            );
     }
 }
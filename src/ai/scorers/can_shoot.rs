@@ -33,7 +33,7 @@ pub fn can_shoot_scorer_system(
                 translation: my_pos,
                 ..
             },
-            CrabMoveWalker { direction: _ },
+            CrabMoveWalker { .. },
             ammo,
         ) = my_query.get(*actor_entity).unwrap(); // FIXME
 
@@ -4,7 +4,7 @@ use big_brain::{
     prelude::*,
 };
 
-use crate::ai::HealthPoints;
+use crate::{ai::HealthPoints, item::medikit::NearestMedikit};
 
 #[derive(Component, Debug)]
 pub struct LowHealth {
@@ -29,13 +29,17 @@ impl ScorerBuilder for LowHealthBuilder {
 }
 
 pub fn low_health_scorer_system(
-    health_query: Query<&HealthPoints>,
+    health_query: Query<(&HealthPoints, Option<&NearestMedikit>)>,
     // Same dance with the Actor here, but now we use look up Score instead of ActionState.
     mut query: Query<(&Actor, &mut Score, &LowHealth)>,
 ) {
     for (Actor(actor), mut score, low_health) in query.iter_mut() {
-        if let Ok(health_points) = health_query.get(*actor) {
-            let value = low_health.evaluator.evaluate(health_points.health as f32);
+        if let Ok((health_points, nearest_medikit)) = health_query.get(*actor) {
+            let mut value = low_health.evaluator.evaluate(health_points.health as f32);
+            // low health is only a reason to do something if there is a medikit within reach
+            if nearest_medikit.and_then(|nearest| nearest.pos).is_none() {
+                value = 0.0;
+            }
             // info!("health score: {}", value);
             score.set(value);
         }
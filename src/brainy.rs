@@ -18,9 +18,10 @@ use crate::{
         util::{Ammo, TargetDistanceProbe},
         HealthPoints,
     },
-    item::ItemContactProbe,
+    hex::marker::{marker_positions, MapMarker, MarkerKind},
+    item::{medikit::NearestMedikit, ItemContactProbe},
     movement::{crab_move::CrabMoveWalker, zap::Zappable},
-    path::Waypoint,
+    path::{TravelDistances, Waypoint},
     sprites,
 };
 
@@ -67,7 +68,9 @@ pub fn spawn_brainy_ferris(
         .insert(CrabMoveWalker::default())
         .insert(TargetDistanceProbe { d: 0.0 })
         .insert(ItemContactProbe::default())
-        .insert(Ammo::default());
+        .insert(Ammo::default())
+        .insert(TravelDistances::new(crate::tune::MEDIKIT_MAX_TRAVEL_TIME))
+        .insert(NearestMedikit::default());
 
     if true {
         entity_commands
@@ -108,6 +111,7 @@ impl Default for SpawnFerrisState {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_brainy_ferris_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -116,6 +120,7 @@ pub fn spawn_brainy_ferris_system(
     mut state: Local<SpawnFerrisState>,
     mut query: Query<(Entity, &mut HealthPoints), With<ThinkerBuilder>>,
     waypoints_query: Query<&Transform, With<Waypoint>>,
+    marker_query: Query<(&MapMarker, &Transform)>,
 ) {
     state.next_increase -= time.delta_seconds();
     if state.next_increase <= 0.0 {
@@ -134,18 +139,32 @@ pub fn spawn_brainy_ferris_system(
     match count.cmp(&state.ferris_count) {
         std::cmp::Ordering::Less => {
             let num_create = state.ferris_count - count;
-            let waypoint_pos = waypoints_query
-                .iter()
-                .map(|transform| transform.translation)
-                .collect::<Vec<_>>();
-
-            if waypoint_pos.len() < num_create {
-                return;
-            }
-
             let mut rng = rand::thread_rng();
 
-            for pos in waypoint_pos.choose_multiple(&mut rng, num_create) {
+            // prefer spawn points placed in the editor, otherwise fall back to random waypoints
+            let spawn_points = marker_positions(&marker_query, MarkerKind::FerrisSpawn);
+            // (at most one ferris per spawn point and frame, the rest follow in the next frames)
+            let spawn_pos = if !spawn_points.is_empty() {
+                spawn_points
+                    .choose_multiple(&mut rng, num_create)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            } else {
+                let waypoint_pos = waypoints_query
+                    .iter()
+                    .map(|transform| transform.translation)
+                    .collect::<Vec<_>>();
+
+                if waypoint_pos.len() < num_create {
+                    return;
+                }
+                waypoint_pos
+                    .choose_multiple(&mut rng, num_create)
+                    .cloned()
+                    .collect()
+            };
+
+            for pos in spawn_pos.iter() {
                 // FIXME: hardcoded z offset is crap
                 spawn_brainy_ferris(&mut commands, &asset_server, *pos + Vec3::Z * 5.0, first);
                 first = false;
@@ -40,6 +40,39 @@ pub fn debug_draw_box(debug_lines: &mut DebugLines, p: Vec3, size: Vec2, duratio
     debug_lines.line(start, end, duration);
 }
 
+// outline of a pointy hex that fits into a box of the given size (i.e. the tile size)
+pub fn debug_draw_hex(debug_lines: &mut DebugLines, p: Vec3, size: Vec2, duration: Option<f32>) {
+    debug_draw_hex_colored(debug_lines, p, size, duration, Color::WHITE);
+}
+
+pub fn debug_draw_hex_colored(
+    debug_lines: &mut DebugLines,
+    p: Vec3,
+    size: Vec2,
+    duration: Option<f32>,
+    color: Color,
+) {
+    let duration = duration.unwrap_or(0.0);
+    let hw = size.x * 0.5;
+    let hh = size.y * 0.5;
+    let corners = [
+        Vec3::new(0.0, hh, 0.0),
+        Vec3::new(hw, hh * 0.5, 0.0),
+        Vec3::new(hw, -hh * 0.5, 0.0),
+        Vec3::new(0.0, -hh, 0.0),
+        Vec3::new(-hw, -hh * 0.5, 0.0),
+        Vec3::new(-hw, hh * 0.5, 0.0),
+    ];
+    let zoff = 5.0;
+    for i in 0..corners.len() {
+        let mut start = p + corners[i];
+        let mut end = p + corners[(i + 1) % corners.len()];
+        start.z = zoff;
+        end.z = zoff;
+        debug_lines.line_colored(start, end, duration, color);
+    }
+}
+
 pub fn debug_draw_line(
     debug_lines: &mut DebugLines,
     mut start: Vec3,
@@ -0,0 +1,187 @@
+use std::{collections::HashMap, fs::File, io::Read, path::Path};
+
+use bevy::prelude::*;
+
+use super::{
+    terrain::{num_terrain_types, terrain_info},
+    tilemap::{HexTileAppearance, HexTileCoord, HexTileIndex},
+    Cube, CUBE_DIRECTIONS,
+};
+
+// bit i is set if the neighbour in CUBE_DIRECTIONS[i] has a different tile type. Missing neighbours (not
+// loaded yet) count as the same type, the mask is updated once they show up.
+#[derive(Component, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct HexTileAutotile {
+    pub mask: u8,
+}
+
+// transition variants of a tileset: per tile type, neighbour mask -> atlas index. Masks without an entry
+// use the plain tile.
+#[derive(Default)]
+pub struct AutotileTable {
+    variants: Vec<HashMap<u8, usize>>,
+}
+
+impl AutotileTable {
+    // yaml file with terrain names as keys, e.g.
+    //   water:
+    //     0b000001: 12
+    //     0b000011: 13
+    pub fn load<P: AsRef<Path>>(filename: P) -> anyhow::Result<Self> {
+        Self::from_reader(File::open(filename)?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> anyhow::Result<Self> {
+        let mut by_name: HashMap<String, HashMap<u8, usize>> = serde_yaml::from_reader(reader)?;
+        let variants = (0..num_terrain_types())
+            .map(|tile_type| {
+                by_name
+                    .remove(terrain_info(tile_type).name)
+                    .unwrap_or_default()
+            })
+            .collect();
+        for name in by_name.keys() {
+            warn!("autotile table: unknown terrain '{}'", name);
+        }
+        Ok(Self { variants })
+    }
+
+    // translate the images of the variants, variants without a new index are dropped
+    pub fn map_indices(self, f: impl Fn(usize) -> Option<usize>) -> Self {
+        let variants = self
+            .variants
+            .into_iter()
+            .map(|masks| {
+                masks
+                    .into_iter()
+                    .filter_map(|(mask, index)| Some((mask, f(index)?)))
+                    .collect()
+            })
+            .collect();
+        Self { variants }
+    }
+
+    pub fn variant(&self, tile_type: usize, mask: u8) -> Option<usize> {
+        self.variants.get(tile_type)?.get(&mask).copied()
+    }
+}
+
+pub fn neighbour_mask(
+    cube: Cube,
+    tile_type: usize,
+    tile_type_at: impl Fn(Cube) -> Option<usize>,
+) -> u8 {
+    CUBE_DIRECTIONS
+        .iter()
+        .enumerate()
+        .filter(|(_, dir)| tile_type_at(cube + **dir).is_some_and(|other| other != tile_type))
+        .fold(0, |mask, (i, _)| mask | (1 << i))
+}
+
+// re-evaluate the masks of changed tiles and of their neighbours
+#[allow(clippy::type_complexity)]
+pub fn autotile_system(
+    index: Res<HexTileIndex>,
+    changed_query: Query<&HexTileCoord, Or<(Changed<HexTileAppearance>, Changed<HexTileCoord>)>>,
+    mut tile_query: Query<(&HexTileAppearance, &mut HexTileAutotile)>,
+) {
+    let mut dirty = Vec::new();
+    for coord in changed_query.iter() {
+        dirty.push(coord.cube);
+        dirty.extend(CUBE_DIRECTIONS.iter().map(|dir| coord.cube + *dir));
+    }
+    if dirty.is_empty() {
+        return;
+    }
+    dirty.sort_by_key(|cube| (cube.x, cube.y, cube.z));
+    dirty.dedup();
+
+    let tile_type_at = |cube: Cube| {
+        let entity = index.tiles.get(&cube)?;
+        tile_query
+            .get(*entity)
+            .ok()
+            .map(|(appearance, _)| appearance.tile_type)
+    };
+    let updates = dirty
+        .iter()
+        .filter_map(|cube| {
+            let entity = *index.tiles.get(cube)?;
+            let tile_type = tile_type_at(*cube)?;
+            Some((entity, neighbour_mask(*cube, tile_type, tile_type_at)))
+        })
+        .collect::<Vec<_>>();
+
+    // only touch tiles with an actual change, the renderers rebuild on change detection
+    for (entity, mask) in updates {
+        if let Ok((_, mut autotile)) = tile_query.get_mut(entity) {
+            if autotile.mask != mask {
+                autotile.mask = mask;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::{
+        terrain::{GROUND, WATER},
+        tileset::{AUTOTILE_ASEPRITE, AUTOTILE_PNG},
+    };
+
+    #[test]
+    fn mask_bits_follow_directions() {
+        let center = Cube::zero();
+        let all_water = |_| Some(WATER);
+        assert_eq!(neighbour_mask(center, WATER, all_water), 0);
+        for (i, dir) in CUBE_DIRECTIONS.iter().enumerate() {
+            let ground_at_dir = |cube| Some(if cube == center + *dir { GROUND } else { WATER });
+            assert_eq!(neighbour_mask(center, WATER, ground_at_dir), 1 << i);
+        }
+        // missing neighbours count as the same type
+        let island = |cube| (cube == center).then_some(GROUND);
+        assert_eq!(neighbour_mask(center, GROUND, island), 0);
+        let lake = |cube| (cube != center).then_some(WATER);
+        assert_eq!(neighbour_mask(center, GROUND, lake), 0b111111);
+    }
+
+    #[test]
+    fn binary_mask_keys() {
+        let yaml = "water:\n  0b000001: 12\n  0b000011: 13\n";
+        let table = AutotileTable::from_reader(yaml.as_bytes()).unwrap();
+        assert_eq!(table.variant(WATER, 0b000001), Some(12));
+        assert_eq!(table.variant(WATER, 0b000011), Some(13));
+        assert_eq!(table.variant(WATER, 0b000010), None);
+        assert_eq!(table.variant(GROUND, 0b000001), None);
+
+        let table = table.map_indices(|index| (index == 12).then_some(100));
+        assert_eq!(table.variant(WATER, 0b000001), Some(100));
+        assert_eq!(table.variant(WATER, 0b000011), None);
+    }
+
+    // the shipped tables have an edge for every neighbour mask of water, wall, ground, moss and rock
+    #[test]
+    fn shipped_tables() {
+        let with_edges = (0..num_terrain_types())
+            .filter(|tile_type| {
+                ["water", "wall", "ground", "moss", "rock"].contains(&terrain_info(*tile_type).name)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(with_edges.len(), 5);
+        for filename in [AUTOTILE_PNG, AUTOTILE_ASEPRITE] {
+            let table = AutotileTable::load(filename).unwrap();
+            for tile_type in with_edges.iter() {
+                for mask in 1..0b1000000 {
+                    assert!(
+                        table.variant(*tile_type, mask).is_some(),
+                        "{} {} {}",
+                        filename,
+                        terrain_info(*tile_type).name,
+                        mask
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use super::{
+    io,
+    layer::HexTileLayers,
+    tilemap::{spawn_tile, HexTileAppearance, HexTileCoord, HexTileIndex, Resources},
+    wavefunction, Cube, Hex,
+};
+
+pub const CHUNK_DIR: &str = "chunks";
+
+mod tune {
+    // chunks are CHUNK_SIZE x CHUNK_SIZE hexes in odd-r coordinates
+    pub const CHUNK_SIZE: i32 = 16;
+    // in chunks around the chunk the camera is in (chebyshev distance)
+    pub const LOAD_RADIUS: i32 = 1;
+    // a bit larger than LOAD_RADIUS, so moving along a chunk border does not constantly load / unload
+    pub const UNLOAD_RADIUS: i32 = 2;
+    // limit hitches from generating chunks
+    pub const CHUNKS_PER_FRAME: usize = 2;
+}
+
+#[derive(Component)]
+pub struct HexChunk {
+    pub pos: IVec2,
+}
+
+#[derive(Default)]
+pub struct HexChunks {
+    // chunk entities (parents of the tiles) of all chunks that are currently in the world
+    pub loaded: HashMap<IVec2, Entity>,
+    // chunks that were unloaded again. Kept in memory (including modifications) until the map is saved.
+    pub cache: HashMap<IVec2, Vec<io::Tile>>,
+    // tiles from map.yaml, used as fixed input when a chunk is generated
+    pub seeds: HashMap<IVec2, Vec<io::Tile>>,
+}
+
+impl HexChunks {
+    // entity of a loaded chunk, spawns an empty one if there is none yet
+    pub fn chunk_entity(
+        &mut self,
+        commands: &mut Commands,
+        resources: &Resources,
+        chunk: IVec2,
+    ) -> Entity {
+        *self.loaded.entry(chunk).or_insert_with(|| {
+            let entity = commands
+                .spawn_bundle(SpatialBundle::default())
+                .insert(HexChunk { pos: chunk })
+                .id();
+            commands.entity(resources.base_entity).add_child(entity);
+            entity
+        })
+    }
+
+    // despawn all loaded chunks (including tiles and everything else that lives in them)
+    pub fn despawn_all(&mut self, commands: &mut Commands) {
+        for (_, entity) in self.loaded.drain() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn cube_to_chunk(cube: Cube) -> IVec2 {
+    let odd_r = cube.to_odd_r();
+    IVec2::new(
+        (odd_r.x as i32).div_euclid(tune::CHUNK_SIZE),
+        (odd_r.y as i32).div_euclid(tune::CHUNK_SIZE),
+    )
+}
+
+pub fn chunk_cubes(chunk: IVec2) -> Vec<Cube> {
+    let origin = chunk * tune::CHUNK_SIZE;
+    (0..tune::CHUNK_SIZE)
+        .flat_map(|y| {
+            (0..tune::CHUNK_SIZE).map(move |x| {
+                Cube::from_odd_r(Vec2::new((origin.x + x) as f32, (origin.y + y) as f32))
+            })
+        })
+        .collect()
+}
+
+pub fn chunk_path(chunk: IVec2) -> PathBuf {
+    Path::new(CHUNK_DIR).join(format!("{}_{}.yaml", chunk.x, chunk.y))
+}
+
+pub fn tile_to_io(
+    coord: &HexTileCoord,
+    appearance: &HexTileAppearance,
+    layers: Option<&HexTileLayers>,
+) -> io::Tile {
+    let axial: Hex = coord.cube.into();
+    let layers = layers.copied().unwrap_or_default();
+    io::Tile {
+        x: axial.q,
+        y: axial.r,
+        t: appearance.tile_type,
+        d: layers.decoration,
+        o: layers.overlay,
+    }
+}
+
+// current tiles of a loaded chunk
+pub fn chunk_tiles(
+    entity: Entity,
+    children_query: &Query<&Children, With<HexChunk>>,
+    tile_query: &Query<(&HexTileCoord, &HexTileAppearance, Option<&HexTileLayers>)>,
+) -> Vec<io::Tile> {
+    children_query
+        .get(entity)
+        .map(|children| {
+            children
+                .iter()
+                .filter_map(|child| tile_query.get(*child).ok())
+                .map(|(coord, appearance, layers)| tile_to_io(coord, appearance, layers))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// split the tiles of map.yaml into generator input per chunk
+pub fn init_seeds(chunks: &mut HexChunks, tiles: Vec<io::Tile>) {
+    for tile in tiles {
+        let cube: Cube = Hex {
+            q: tile.x,
+            r: tile.y,
+        }
+        .into();
+        chunks
+            .seeds
+            .entry(cube_to_chunk(cube))
+            .or_default()
+            .push(tile);
+    }
+}
+
+// tiles for a chunk that is not in the world: from memory, from disk or freshly generated
+fn chunk_source_tiles(
+    chunks: &HexChunks,
+    chunk: IVec2,
+    children_query: &Query<&Children, With<HexChunk>>,
+    tile_query: &Query<(&HexTileCoord, &HexTileAppearance, Option<&HexTileLayers>)>,
+) -> Vec<io::Tile> {
+    if let Some(tiles) = chunks.cache.get(&chunk) {
+        return tiles.clone();
+    }
+    match io::Tilemap::load(chunk_path(chunk)) {
+        Ok(tilemap) => return tilemap.tiles,
+        Err(err) => debug!("no chunk file for {:?}: {:?}", chunk, err),
+    }
+
+    let seeds = chunks.seeds.get(&chunk).map(Vec::as_slice).unwrap_or(&[]);
+    let seed_tiles: HashMap<Cube, &io::Tile> = seeds
+        .iter()
+        .map(|tile| {
+            (
+                Hex {
+                    q: tile.x,
+                    r: tile.y,
+                }
+                .into(),
+                tile,
+            )
+        })
+        .collect();
+
+    // seeds are fixed, the loaded neighbor chunks constrain the border so the terrain continues
+    let mut fixed: HashMap<Cube, usize> = seed_tiles
+        .iter()
+        .map(|(cube, tile)| (*cube, tile.t))
+        .collect();
+    for y in -1..=1 {
+        for x in -1..=1 {
+            if let Some(entity) = chunks.loaded.get(&(chunk + IVec2::new(x, y))) {
+                for tile in chunk_tiles(*entity, children_query, tile_query) {
+                    fixed.insert(
+                        Hex {
+                            q: tile.x,
+                            r: tile.y,
+                        }
+                        .into(),
+                        tile.t,
+                    );
+                }
+            }
+        }
+    }
+
+    wavefunction::generate(&chunk_cubes(chunk), &fixed)
+        .into_iter()
+        .map(|(cube, tile_type)| {
+            let axial: Hex = cube.into();
+            match seed_tiles.get(&cube) {
+                // seeds also carry tile types / layers that are unknown to the wavefunction
+                Some(seed) => io::Tile {
+                    x: seed.x,
+                    y: seed.y,
+                    t: seed.t,
+                    d: seed.d,
+                    o: seed.o,
+                },
+                None => io::Tile {
+                    x: axial.q,
+                    y: axial.r,
+                    t: tile_type,
+                    d: None,
+                    o: None,
+                },
+            }
+        })
+        .collect()
+}
+
+pub fn spawn_tiles(
+    commands: &mut Commands,
+    resources: &Resources,
+    chunks: &mut HexChunks,
+    index: &mut HexTileIndex,
+    tiles: &[io::Tile],
+) {
+    for io::Tile { x, y, t, d, o } in tiles.iter() {
+        let cube = Hex { q: *x, r: *y }.into();
+        let entity = spawn_tile(commands, resources, chunks, index, cube, *t);
+        let layers = HexTileLayers {
+            decoration: *d,
+            overlay: *o,
+        };
+        if !layers.is_empty() {
+            commands.entity(entity).insert(layers);
+        }
+    }
+}
+
+// load chunks around the camera and unload the ones that are far away
+pub fn stream_chunks_system(
+    mut commands: Commands,
+    resources: Res<Resources>,
+    mut chunks: ResMut<HexChunks>,
+    mut index: ResMut<HexTileIndex>,
+    camera_query: Query<&GlobalTransform, With<Camera2d>>,
+    children_query: Query<&Children, With<HexChunk>>,
+    tile_query: Query<(&HexTileCoord, &HexTileAppearance, Option<&HexTileLayers>)>,
+) {
+    let camera_transform = match camera_query.get_single() {
+        Ok(transform) => transform,
+        Err(_) => return,
+    };
+    let center = cube_to_chunk(resources.world_to_cube(camera_transform.translation()));
+
+    let unload = chunks
+        .loaded
+        .iter()
+        .filter(|(chunk, _)| {
+            let d = (**chunk - center).abs();
+            d.x.max(d.y) > tune::UNLOAD_RADIUS
+        })
+        .map(|(chunk, entity)| (*chunk, *entity))
+        .collect::<Vec<_>>();
+    for (chunk, entity) in unload {
+        debug!("unload chunk {:?}", chunk);
+        let tiles = chunk_tiles(entity, &children_query, &tile_query);
+        chunks.cache.insert(chunk, tiles);
+        chunks.loaded.remove(&chunk);
+        for tile in children_query.get(entity).into_iter().flatten() {
+            index.remove(*tile);
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let mut load = Vec::new();
+    for y in -tune::LOAD_RADIUS..=tune::LOAD_RADIUS {
+        for x in -tune::LOAD_RADIUS..=tune::LOAD_RADIUS {
+            let chunk = center + IVec2::new(x, y);
+            if !chunks.loaded.contains_key(&chunk) {
+                load.push(chunk);
+            }
+        }
+    }
+    // nearest first
+    load.sort_by_key(|chunk| (*chunk - center).abs().max_element());
+
+    for chunk in load.into_iter().take(tune::CHUNKS_PER_FRAME) {
+        debug!("load chunk {:?}", chunk);
+        let tiles = chunk_source_tiles(&chunks, chunk, &children_query, &tile_query);
+        chunks.cache.remove(&chunk);
+        chunks.chunk_entity(&mut commands, &resources, chunk);
+        spawn_tiles(&mut commands, &resources, &mut chunks, &mut index, &tiles);
+    }
+}
+
+// write all chunks that are loaded or in memory to the chunk directory
+pub fn save_chunks(
+    chunks: &HexChunks,
+    children_query: &Query<&Children, With<HexChunk>>,
+    tile_query: &Query<(&HexTileCoord, &HexTileAppearance, Option<&HexTileLayers>)>,
+) -> anyhow::Result<()> {
+    std::fs::create_dir_all(CHUNK_DIR)?;
+    let loaded = chunks
+        .loaded
+        .iter()
+        .map(|(chunk, entity)| (*chunk, chunk_tiles(*entity, children_query, tile_query)));
+    let cached = chunks
+        .cache
+        .iter()
+        .map(|(chunk, tiles)| (*chunk, tiles.clone()));
+    for (chunk, tiles) in loaded.chain(cached) {
+        let tilemap = io::Tilemap {
+            tiles,
+            markers: Vec::new(),
+        };
+        tilemap.save(chunk_path(chunk))?;
+    }
+    Ok(())
+}
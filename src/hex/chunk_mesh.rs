@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    sprite::Mesh2dHandle,
+};
+
+use super::{
+    autotile::HexTileAutotile,
+    chunk::HexChunk,
+    layer::{ground_color, LayerVisibility},
+    tilemap::{HexTileAppearance, HexTileCoord, Resources},
+    tileset::Tileset,
+    Cube,
+};
+
+// one quad per tile, textured from the tile atlas. Positions are in world space (chunks sit at the origin).
+pub fn build_chunk_mesh(
+    tiles: impl IntoIterator<Item = (Cube, usize)>,
+    tile_size: Vec2,
+    atlas: &TextureAtlas,
+) -> Mesh {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+
+    let half = tile_size * 0.5;
+    for (cube, tile_type) in tiles {
+        let rect = match atlas.textures.get(tile_type) {
+            Some(rect) => rect,
+            None => continue,
+        };
+        let center = cube.to_odd_r_screen() * tile_size;
+        let min = center - half;
+        let max = center + half;
+        // texture coordinates go top to bottom
+        let uv_min = rect.min / atlas.size;
+        let uv_max = rect.max / atlas.size;
+
+        let base = positions.len() as u32;
+        positions.extend([
+            [min.x, min.y, 0.0],
+            [max.x, min.y, 0.0],
+            [max.x, max.y, 0.0],
+            [min.x, max.y, 0.0],
+        ]);
+        uvs.extend([
+            [uv_min.x, uv_max.y],
+            [uv_max.x, uv_max.y],
+            [uv_max.x, uv_min.y],
+            [uv_min.x, uv_min.y],
+        ]);
+        indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+// rebuild the mesh of every chunk with new, changed or removed tiles. A new tileset atlas rebuilds all chunks,
+// animation frames only the chunks with animated tiles.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_chunk_meshes_system(
+    mut commands: Commands,
+    resources: Res<Resources>,
+    tileset: Res<Tileset>,
+    layer_visibility: Res<LayerVisibility>,
+    atlases: Res<Assets<TextureAtlas>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut material: Local<Option<(Handle<TextureAtlas>, Handle<ColorMaterial>)>>,
+    mut animated_chunks: Local<HashSet<Entity>>,
+    changed_query: Query<
+        &Parent,
+        Or<(
+            Changed<HexTileAppearance>,
+            Changed<HexTileCoord>,
+            Changed<HexTileAutotile>,
+        )>,
+    >,
+    // tiles that are erased or despawned only show up as a change of the chunk's children
+    children_changed_query: Query<Entity, (With<HexChunk>, Changed<Children>)>,
+    chunk_query: Query<(Entity, &Children), With<HexChunk>>,
+    tile_query: Query<(&HexTileCoord, &HexTileAppearance, &HexTileAutotile)>,
+) {
+    // the chunks are the parents of the tiles (and their layer sprites), so the ground is hidden through the
+    // material instead of the chunk's Visibility
+    if layer_visibility.is_changed() {
+        if let Some(material) = material
+            .as_ref()
+            .and_then(|(_, handle)| materials.get_mut(handle))
+        {
+            material.color = ground_color(layer_visibility.ground);
+        }
+    }
+
+    let mut dirty = changed_query
+        .iter()
+        .map(|parent| parent.get())
+        .chain(children_changed_query.iter())
+        .collect::<HashSet<_>>();
+    // the meshes are built for the atlas of the material
+    let atlas_changed = material
+        .as_ref()
+        .is_none_or(|(handle, _)| *handle != tileset.atlas);
+    if atlas_changed {
+        dirty.extend(chunk_query.iter().map(|(entity, _)| entity));
+    } else if tileset.is_changed() {
+        dirty.extend(animated_chunks.iter().copied());
+    }
+    if dirty.is_empty() {
+        return;
+    }
+    let atlas = match atlases.get(&tileset.atlas) {
+        Some(atlas) => atlas,
+        None => return,
+    };
+    // the aseprite tileset comes with its own texture
+    let material = match &*material {
+        Some((handle, material)) if *handle == tileset.atlas => material.clone(),
+        _ => {
+            let handle = materials.add(ColorMaterial {
+                color: ground_color(layer_visibility.ground),
+                texture: Some(atlas.texture.clone()),
+            });
+            *material = Some((tileset.atlas.clone(), handle.clone()));
+            handle
+        }
+    };
+
+    for entity in dirty {
+        let children = match chunk_query.get(entity) {
+            Ok((_, children)) => children,
+            Err(_) => {
+                animated_chunks.remove(&entity);
+                continue;
+            }
+        };
+        let tiles = children
+            .iter()
+            .filter_map(|child| tile_query.get(*child).ok());
+        if tiles
+            .clone()
+            .any(|(_, appearance, _)| tileset.is_animated(appearance.tile_type))
+        {
+            animated_chunks.insert(entity);
+        } else {
+            animated_chunks.remove(&entity);
+        }
+        let tiles = tiles.map(|(coord, appearance, autotile)| {
+            (
+                coord.cube,
+                tileset.tile_index(appearance.tile_type, autotile.mask),
+            )
+        });
+        let mesh = build_chunk_mesh(tiles, resources.tile_size, atlas);
+        commands
+            .entity(entity)
+            .insert(Mesh2dHandle(meshes.add(mesh)))
+            .insert(material.clone());
+    }
+}
+
+pub struct HexChunkMeshRendererPlugin;
+
+impl Plugin for HexChunkMeshRendererPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_chunk_meshes_system);
+    }
+}
@@ -0,0 +1,413 @@
+use bevy::prelude::*;
+
+use super::Cube;
+
+// circle vs pointy hex collision. Hexes fit into a box of tile_size and are centered on
+// cube.to_odd_r_screen() * tile_size, so neighbouring hexes share their edges exactly (no gaps between
+// diagonal neighbours, no overlap).
+
+mod tune {
+    // push-out passes per move; more than one is needed where walls meet at an angle
+    pub const MAX_ITERATIONS: usize = 4;
+    // hexes around the mover that are checked. 2 is enough for circles smaller than half a tile.
+    pub const SEARCH_RADIUS: i32 = 2;
+    // touching is not overlapping. Without some slack, rounding errors keep pushing a circle that rests
+    // against a wall.
+    pub const EPSILON: f32 = 1e-3;
+}
+
+pub fn hex_center(cube: Cube, tile_size: Vec2) -> Vec2 {
+    cube.to_odd_r_screen() * tile_size
+}
+
+pub fn hex_corners(center: Vec2, tile_size: Vec2) -> [Vec2; 6] {
+    let hw = tile_size.x * 0.5;
+    let hh = tile_size.y * 0.5;
+    // counter clockwise, starting at the top
+    [
+        center + Vec2::new(0.0, hh),
+        center + Vec2::new(-hw, hh * 0.5),
+        center + Vec2::new(-hw, -hh * 0.5),
+        center + Vec2::new(0.0, -hh),
+        center + Vec2::new(hw, -hh * 0.5),
+        center + Vec2::new(hw, hh * 0.5),
+    ]
+}
+
+fn closest_point_on_segment(p: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let t = ((p - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0);
+    a + ab * t
+}
+
+// vector that moves the circle out of the hex, None if they do not overlap
+pub fn circle_hex_penetration(
+    pos: Vec2,
+    radius: f32,
+    center: Vec2,
+    tile_size: Vec2,
+) -> Option<Vec2> {
+    let corners = hex_corners(center, tile_size);
+    let edges = (0..6).map(|i| (corners[i], corners[(i + 1) % 6]));
+
+    // signed distance to the edge lines (outward normals of a ccw polygon)
+    let (max_dist, max_normal) = edges
+        .clone()
+        .map(|(a, b)| {
+            let normal = Vec2::new(b.y - a.y, a.x - b.x).normalize();
+            ((pos - a).dot(normal), normal)
+        })
+        .fold((f32::NEG_INFINITY, Vec2::ZERO), |acc, (dist, normal)| {
+            if dist > acc.0 {
+                (dist, normal)
+            } else {
+                acc
+            }
+        });
+    if max_dist <= 0.0 {
+        // center is inside: push out through the nearest edge
+        return Some(max_normal * (radius - max_dist));
+    }
+
+    let closest = edges
+        .map(|(a, b)| closest_point_on_segment(pos, a, b))
+        .min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)))?;
+    let d = pos - closest;
+    let dist = d.length();
+    (dist < radius - tune::EPSILON).then(|| d / dist * (radius - dist))
+}
+
+fn cubes_around(pos: Vec2, tile_size: Vec2) -> impl Iterator<Item = Cube> {
+    let center = Cube::from_odd_r_screen((pos + tile_size * 0.5) / tile_size);
+    let r = tune::SEARCH_RADIUS;
+    (-r..=r).flat_map(move |x| {
+        (-r..=r)
+            .filter(move |z| (-x - z).abs() <= r)
+            .map(move |z| center + Cube::new(x, -x - z, z))
+    })
+}
+
+// first solid hex the circle overlaps
+pub fn circle_overlaps_solid(
+    pos: Vec2,
+    radius: f32,
+    tile_size: Vec2,
+    is_solid: impl Fn(Cube) -> bool,
+) -> Option<Cube> {
+    cubes_around(pos, tile_size).find(|cube| {
+        is_solid(*cube)
+            && circle_hex_penetration(pos, radius, hex_center(*cube, tile_size), tile_size)
+                .is_some()
+    })
+}
+
+// move a circle by delta and resolve overlaps with solid hexes. Only the blocked component of the movement
+// is removed, so the mover slides along walls. Returns the actual movement.
+pub fn slide_circle(
+    pos: Vec2,
+    delta: Vec2,
+    radius: f32,
+    tile_size: Vec2,
+    is_solid: impl Fn(Cube) -> bool,
+) -> Vec2 {
+    let mut target = pos + delta;
+    for _ in 0..tune::MAX_ITERATIONS {
+        let push = cubes_around(target, tile_size)
+            .filter(|cube| is_solid(*cube))
+            .filter_map(|cube| {
+                circle_hex_penetration(target, radius, hex_center(cube, tile_size), tile_size)
+            })
+            .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+        match push {
+            Some(push) => target += push,
+            None => return target - pos,
+        }
+    }
+    // could not resolve (e.g. squeezed into a gap that is too small): stay put
+    Vec2::ZERO
+}
+
+// first contact of a moving circle, toi is the fraction of the movement (0..=1) at which it happens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepHit {
+    pub toi: f32,
+    pub normal: Vec2,
+}
+
+// ray pos + t * delta against a circle, t in 0..=1
+fn ray_circle(pos: Vec2, delta: Vec2, center: Vec2, radius: f32) -> Option<SweepHit> {
+    let m = pos - center;
+    let a = delta.length_squared();
+    let b = m.dot(delta);
+    let c = m.length_squared() - radius * radius;
+    if c <= 0.0 {
+        // already inside
+        let normal = m.try_normalize().unwrap_or(-delta.normalize_or_zero());
+        return Some(SweepHit { toi: 0.0, normal });
+    }
+    if a == 0.0 || b >= 0.0 {
+        return None;
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let toi = (-b - discriminant.sqrt()) / a;
+    (toi <= 1.0).then(|| SweepHit {
+        toi,
+        normal: (m + delta * toi) / radius,
+    })
+}
+
+// moving circle against another (static) circle
+pub fn sweep_circle_circle(
+    pos: Vec2,
+    delta: Vec2,
+    radius: f32,
+    other: Vec2,
+    other_radius: f32,
+) -> Option<SweepHit> {
+    ray_circle(pos, delta, other, radius + other_radius)
+}
+
+// moving circle against a hex: ray against the hex grown by the radius (edges pushed out, rounded corners)
+pub fn sweep_circle_hex(
+    pos: Vec2,
+    delta: Vec2,
+    radius: f32,
+    center: Vec2,
+    tile_size: Vec2,
+) -> Option<SweepHit> {
+    if let Some(push) = circle_hex_penetration(pos, radius, center, tile_size) {
+        return Some(SweepHit {
+            toi: 0.0,
+            normal: push.normalize_or_zero(),
+        });
+    }
+    let corners = hex_corners(center, tile_size);
+    let edge_hits = (0..6).filter_map(|i| {
+        let (a, b) = (corners[i], corners[(i + 1) % 6]);
+        let normal = Vec2::new(b.y - a.y, a.x - b.x).normalize();
+        let approach = delta.dot(normal);
+        if approach >= 0.0 {
+            return None;
+        }
+        let a = a + normal * radius;
+        let toi = (pos - a).dot(normal) / -approach;
+        let edge = b - a + normal * radius;
+        let along = (pos + delta * toi - a).dot(edge) / edge.length_squared();
+        ((0.0..=1.0).contains(&toi) && (0.0..=1.0).contains(&along))
+            .then_some(SweepHit { toi, normal })
+    });
+    let corner_hits = corners
+        .iter()
+        .filter_map(|corner| ray_circle(pos, delta, *corner, radius));
+    edge_hits
+        .chain(corner_hits)
+        .min_by(|a, b| a.toi.total_cmp(&b.toi))
+}
+
+// first solid hex hit by a moving circle. Unlike slide_circle this does not miss walls for big movements.
+pub fn sweep_circle(
+    pos: Vec2,
+    delta: Vec2,
+    radius: f32,
+    tile_size: Vec2,
+    is_solid: impl Fn(Cube) -> bool,
+) -> Option<(Cube, SweepHit)> {
+    // sample the path densely enough that the hexes around the samples cover everything it touches
+    let steps = (delta.length() / (tile_size.min_element() * 0.5)).ceil() as usize;
+    let mut candidates = (0..=steps)
+        .flat_map(|i| {
+            let t = if steps == 0 {
+                0.0
+            } else {
+                i as f32 / steps as f32
+            };
+            cubes_around(pos + delta * t, tile_size)
+        })
+        .filter(|cube| is_solid(*cube))
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|cube| (cube.x, cube.y, cube.z));
+    candidates.dedup();
+
+    candidates
+        .into_iter()
+        .filter_map(|cube| {
+            sweep_circle_hex(pos, delta, radius, hex_center(cube, tile_size), tile_size)
+                .map(|hit| (cube, hit))
+        })
+        .min_by(|(_, a), (_, b)| a.toi.total_cmp(&b.toi))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    const TILE_SIZE: Vec2 = Vec2::new(18.0, 20.0);
+    const RADIUS: f32 = 3.0;
+
+    fn walls(cubes: &[Cube]) -> impl Fn(Cube) -> bool {
+        let walls = cubes.iter().copied().collect::<HashSet<_>>();
+        move |cube| walls.contains(&cube)
+    }
+
+    fn odd_r(x: i32, y: i32) -> Cube {
+        Cube::from_odd_r(Vec2::new(x as f32, y as f32))
+    }
+
+    fn center(x: i32, y: i32) -> Vec2 {
+        hex_center(odd_r(x, y), TILE_SIZE)
+    }
+
+    #[test]
+    fn free_movement_is_unchanged() {
+        let delta = Vec2::new(1.0, 0.5);
+        let moved = slide_circle(center(0, 0), delta, RADIUS, TILE_SIZE, walls(&[]));
+        assert_eq!(moved, delta);
+    }
+
+    #[test]
+    fn blocked_by_wall() {
+        // wall east of the mover, which stands right at the shared edge
+        let pos = center(0, 0) + Vec2::new(9.0 - RADIUS, 0.0);
+        let moved = slide_circle(pos, Vec2::X, RADIUS, TILE_SIZE, walls(&[odd_r(1, 0)]));
+        assert!(moved.x.abs() < 1e-4, "{:?}", moved);
+    }
+
+    #[test]
+    fn slides_along_wall() {
+        // moving diagonally into a vertical wall edge keeps the vertical component
+        let pos = center(0, 0) + Vec2::new(9.0 - RADIUS, 0.0);
+        let moved = slide_circle(
+            pos,
+            Vec2::new(1.0, 1.0),
+            RADIUS,
+            TILE_SIZE,
+            walls(&[odd_r(1, 0)]),
+        );
+        assert!(moved.x.abs() < 1e-4, "{:?}", moved);
+        assert!((moved.y - 1.0).abs() < 1e-4, "{:?}", moved);
+    }
+
+    #[test]
+    fn slides_around_corner() {
+        // running into the lower corner of a wall hex deflects instead of getting stuck
+        let wall = odd_r(0, 1);
+        let corner = center(0, 1) - Vec2::new(0.0, 10.0);
+        let pos = corner - Vec2::new(0.5, RADIUS + 0.5);
+        let moved = slide_circle(pos, Vec2::new(0.0, 1.0), RADIUS, TILE_SIZE, walls(&[wall]));
+        assert!(moved.length() > 0.1, "stuck at corner: {:?}", moved);
+        assert!(moved.x < 0.0, "{:?}", moved);
+        let end = pos + moved;
+        assert!(
+            circle_overlaps_solid(end, RADIUS - 1e-3, TILE_SIZE, walls(&[wall])).is_none(),
+            "{:?}",
+            end
+        );
+    }
+
+    #[test]
+    fn walk_through_corridor() {
+        // one hex wide corridor going east, walls in the rows above and below
+        let solid = (-2..8)
+            .flat_map(|x| [odd_r(x, 1), odd_r(x, -1)])
+            .collect::<Vec<_>>();
+        let is_solid = walls(&solid);
+        let mut pos = center(0, 0);
+        for _ in 0..100 {
+            pos += slide_circle(pos, Vec2::new(0.5, 0.0), RADIUS, TILE_SIZE, &is_solid);
+        }
+        assert!((pos - center(0, 0) - Vec2::new(50.0, 0.0)).length() < 1e-3);
+
+        // pushing slightly into the wall while walking slides along it. The wall is a zigzag of hex edges
+        // (about 30 degrees), so steeper pushes rightfully end up stuck in a notch.
+        let mut pos = center(0, 0);
+        for _ in 0..100 {
+            pos += slide_circle(pos, Vec2::new(0.5, 0.1), RADIUS, TILE_SIZE, &is_solid);
+        }
+        assert!(pos.x - center(0, 0).x > 40.0, "{:?}", pos);
+        assert!(circle_overlaps_solid(pos, RADIUS - 1e-3, TILE_SIZE, &is_solid).is_none());
+    }
+
+    #[test]
+    fn no_gap_between_diagonal_walls() {
+        // a wall and its north east neighbour. Heading into the notch where they meet (the top corner of the
+        // lower wall) stops in front of it, the circle never gets into the walls.
+        let solid = [odd_r(0, 0), odd_r(0, 1)];
+        let is_solid = walls(&solid);
+        let notch = center(0, 0) + Vec2::new(0.0, 10.0);
+        let dir = Vec2::new(1.0, -1.0).normalize();
+        let mut pos = notch + Vec2::new(-10.0, 10.0);
+        for _ in 0..100 {
+            pos += slide_circle(pos, dir * 0.5, RADIUS, TILE_SIZE, &is_solid);
+            assert!(circle_overlaps_solid(pos, RADIUS - 1e-3, TILE_SIZE, &is_solid).is_none());
+        }
+        assert!((pos - notch).dot(dir) < 0.0, "{:?}", pos);
+        assert!(pos.distance(notch) < RADIUS * 2.0, "{:?}", pos);
+    }
+
+    #[test]
+    fn sweep_does_not_tunnel() {
+        // a single wall, the movement starts in front of it and would end behind it
+        let wall = odd_r(1, 0);
+        let pos = center(-1, 0);
+        let delta = Vec2::new(60.0, 0.0);
+        let (cube, hit) = sweep_circle(pos, delta, RADIUS, TILE_SIZE, walls(&[wall])).unwrap();
+        assert_eq!(cube, wall);
+        // the west edge of the wall is at x = 9
+        let contact = pos + delta * hit.toi;
+        assert!((contact.x - (9.0 - RADIUS)).abs() < 1e-3, "{:?}", contact);
+        assert!(
+            (hit.normal - Vec2::new(-1.0, 0.0)).length() < 1e-4,
+            "{:?}",
+            hit
+        );
+    }
+
+    #[test]
+    fn sweep_hits_corner() {
+        // passing just below the bottom corner of a wall hex grazes it
+        let wall = odd_r(0, 1);
+        let corner = center(0, 1) - Vec2::new(0.0, 10.0);
+        let pos = corner + Vec2::new(-30.0, -RADIUS + 0.5);
+        let hit =
+            sweep_circle_hex(pos, Vec2::new(60.0, 0.0), RADIUS, center(0, 1), TILE_SIZE).unwrap();
+        let contact = pos + Vec2::new(60.0, 0.0) * hit.toi;
+        // touching, but not overlapping
+        assert!(circle_hex_penetration(contact, RADIUS + 0.01, center(0, 1), TILE_SIZE).is_some());
+        assert!(circle_hex_penetration(contact, RADIUS - 0.01, center(0, 1), TILE_SIZE).is_none());
+        assert!(hit.normal.x < 0.0 && hit.normal.y < 0.0, "{:?}", hit);
+
+        // a bit further down it misses
+        let pos = pos - Vec2::new(0.0, 1.0);
+        assert!(
+            sweep_circle(pos, Vec2::new(60.0, 0.0), RADIUS, TILE_SIZE, walls(&[wall])).is_none()
+        );
+    }
+
+    #[test]
+    fn sweep_circles() {
+        let hit = sweep_circle_circle(
+            Vec2::ZERO,
+            Vec2::new(10.0, 0.0),
+            1.0,
+            Vec2::new(8.0, 0.0),
+            2.0,
+        )
+        .unwrap();
+        assert!((hit.toi - 0.5).abs() < 1e-5, "{:?}", hit);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+        // moving away
+        assert!(sweep_circle_circle(
+            Vec2::ZERO,
+            Vec2::new(-10.0, 0.0),
+            1.0,
+            Vec2::new(8.0, 0.0),
+            2.0
+        )
+        .is_none());
+    }
+}
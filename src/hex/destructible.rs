@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use super::{
+    terrain::terrain_info,
+    tilemap::{HexTileAppearance, HexTileIndex},
+    Cube,
+};
+
+// something (e.g. a pew) hit the tile at cube
+pub struct TileHitEvent {
+    pub cube: Cube,
+    pub damage: f32,
+}
+
+// damage taken since the tile became its current type
+#[derive(Component, Default)]
+pub struct HexTileDamage {
+    pub damage: f32,
+}
+
+// destructible tiles turn into their next damage stage (see TerrainInfo::destroyed_into) once they took their
+// hit points. Collision, waypoints and rendering all follow the tile type, so nothing else needs to be done.
+pub fn tile_damage_system(
+    mut commands: Commands,
+    mut hit_events: EventReader<TileHitEvent>,
+    index: Res<HexTileIndex>,
+    mut tile_query: Query<(&mut HexTileAppearance, Option<&mut HexTileDamage>)>,
+) {
+    // sum up first, a tile can be hit several times per frame
+    let mut hits = HashMap::<Cube, f32>::new();
+    for TileHitEvent { cube, damage } in hit_events.iter() {
+        *hits.entry(*cube).or_default() += *damage;
+    }
+
+    for (cube, damage) in hits {
+        let entity = match index.tiles.get(&cube) {
+            Some(entity) => *entity,
+            None => continue,
+        };
+        let (mut appearance, tile_damage) = match tile_query.get_mut(entity) {
+            Ok(tile) => tile,
+            Err(_) => continue,
+        };
+        let info = terrain_info(appearance.tile_type);
+        if info.hit_points <= 0.0 {
+            continue;
+        }
+        let total = tile_damage.as_ref().map_or(0.0, |d| d.damage) + damage;
+        if total < info.hit_points {
+            match tile_damage {
+                Some(mut tile_damage) => tile_damage.damage = total,
+                None => {
+                    commands
+                        .entity(entity)
+                        .insert(HexTileDamage { damage: total });
+                }
+            }
+            continue;
+        }
+        debug!(
+            "tile {:?} destroyed: {} -> {}",
+            cube,
+            info.name,
+            terrain_info(info.destroyed_into).name
+        );
+        appearance.tile_type = info.destroyed_into;
+        // excess damage is lost, the next stage starts fresh
+        commands.entity(entity).remove::<HexTileDamage>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::Events;
+
+    use super::*;
+    use crate::hex::terrain::{CRACKED_WALL, RUBBLE, WALL};
+
+    #[test]
+    fn wall_breaks_in_stages() {
+        let mut world = World::new();
+        world.init_resource::<Events<TileHitEvent>>();
+        let cube = Cube::new(1, -1, 0);
+        let tile = world
+            .spawn()
+            .insert(HexTileAppearance { tile_type: WALL })
+            .id();
+        let mut index = HexTileIndex::default();
+        index.insert(cube, tile);
+        world.insert_resource(index);
+        let mut stage = SystemStage::single(tile_damage_system);
+
+        let mut hit = |world: &mut World, damages: &[f32]| {
+            for damage in damages {
+                world.send_event(TileHitEvent {
+                    cube,
+                    damage: *damage,
+                });
+            }
+            stage.run(world);
+            let tile_type = world.get::<HexTileAppearance>(tile).unwrap().tile_type;
+            let damage = world.get::<HexTileDamage>(tile).map(|d| d.damage);
+            (tile_type, damage)
+        };
+
+        let hit_points = terrain_info(WALL).hit_points;
+        assert_eq!(
+            hit(&mut world, &[hit_points - 1.0]),
+            (WALL, Some(hit_points - 1.0))
+        );
+        // excess damage does not carry over to the next stage
+        assert_eq!(hit(&mut world, &[hit_points]), (CRACKED_WALL, None));
+        assert!(!terrain_info(CRACKED_WALL).walkable);
+
+        // hits of one frame add up
+        let hit_points = terrain_info(CRACKED_WALL).hit_points;
+        assert_eq!(
+            hit(&mut world, &[hit_points * 0.5, hit_points * 0.5]),
+            (RUBBLE, None)
+        );
+        assert!(terrain_info(RUBBLE).walkable);
+
+        // rubble is the last stage
+        assert_eq!(hit(&mut world, &[1000.0]), (RUBBLE, None));
+        // hits on hexes without a tile are ignored
+        world.send_event(TileHitEvent {
+            cube: Cube::zero(),
+            damage: 1000.0,
+        });
+        stage.run(&mut world);
+    }
+}
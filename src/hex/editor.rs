@@ -1,40 +1,147 @@
-use bevy::{math::Vec3Swizzles, prelude::*};
+use std::collections::HashMap;
+
+use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
+use bevy_prototype_debug_lines::DebugLines;
 
-use crate::{hex::Cube, pointer::ClickEvent};
+use crate::{
+    debug::{debug_draw_cross, debug_draw_hex, debug_draw_hex_colored},
+    hex::Cube,
+    item::medikit::Medikit,
+    movement::zap::Zappable,
+    path::Waypoint,
+    pointer::{ClickEvent, PrimaryPointerPos},
+    state::AppState,
+    InputTarget,
+};
 
 use super::{
-    io,
-    tilemap::{HexTileAppearance, HexTileCoord, Resources},
+    chunk::{save_chunks, HexChunk, HexChunks},
+    layer::{HexLayer, HexTileLayers, LayerVisibility},
+    marker::{self, MapMarker, MarkerKind},
+    stamp::{self, Stamp},
+    symmetry::{RotationalSymmetry, Symmetry},
+    terrain::{self, terrain_info},
+    tilemap::{
+        spawn_tile, tilemap_snapshot, HexTileAppearance, HexTileCoord, HexTileIndex, Resources,
+    },
     Hex,
 };
 
-#[derive(PartialEq)]
+mod tune {
+    pub const CAMERA_PAN_SPEED: f32 = 200.0;
+}
+
+#[derive(PartialEq, Clone, Copy, Default)]
 enum ClickMode {
+    #[default]
     Wall,
     Ground,
     Water,
+    Select,
+    Stamp,
+    SymmetryCenter,
+    Marker(MarkerKind),
+    MoveMarker,
+    DeleteMarker,
+    // paint / erase layer_tile on the active layer
+    PaintLayer,
+    EraseLayer,
     // Fill,
     // Probe,
     // GoThere,
 }
 
-impl Default for ClickMode {
-    fn default() -> Self {
-        ClickMode::Wall
-    }
-}
-
 #[derive(Default)]
 pub struct InteractionState {
     click_mode: ClickMode,
+    // first corner of a selection that is currently being made
+    selection_anchor: Option<Cube>,
+    // corners of the selected (odd-r) rectangle
+    selection: Option<(Cube, Cube)>,
+    clipboard: Stamp,
+    stamp_name: String,
+    stamp_library: Option<Vec<String>>,
+    symmetry: Symmetry,
+    // marker that was picked up in MoveMarker mode
+    moving_marker: Option<Entity>,
+    layer: HexLayer,
+    layer_tile: usize,
+}
+
+// all hexes in the odd-r 'rectangle' spanned by two corners
+fn selection_cubes(a: Cube, b: Cube) -> impl Iterator<Item = Cube> {
+    let a = a.to_odd_r();
+    let b = b.to_odd_r();
+    let min = a.min(b);
+    let max = a.max(b);
+    (min.y as i32..=max.y as i32).flat_map(move |row| {
+        (min.x as i32..=max.x as i32)
+            .map(move |col| Cube::from_odd_r(Vec2::new(col as f32, row as f32)))
+    })
+}
+
+// overwrite the tile at cube or spawn a new one if there is none yet. The upper layers are replaced as well if
+// layers is given, otherwise they are kept.
+fn set_tile(
+    commands: &mut Commands,
+    resources: &Resources,
+    chunks: &mut HexChunks,
+    index: &mut HexTileIndex,
+    cube: Cube,
+    tile_type: usize,
+    layers: Option<HexTileLayers>,
+) {
+    let entity = if let Some(entity) = index.tiles.get(&cube) {
+        commands
+            .entity(*entity)
+            .insert(HexTileAppearance { tile_type });
+        *entity
+    } else {
+        spawn_tile(commands, resources, chunks, index, cube, tile_type)
+    };
+    if let Some(layers) = layers {
+        commands.entity(entity).insert(layers);
+    }
+}
+
+// set or clear the tile of an upper layer. There must already be a ground tile at cube.
+fn set_layer_tile(
+    commands: &mut Commands,
+    index: &HexTileIndex,
+    tile_query: &Query<(&HexTileAppearance, Option<&HexTileLayers>)>,
+    cube: Cube,
+    layer: HexLayer,
+    tile_type: Option<usize>,
+) {
+    let entity = match index.tiles.get(&cube) {
+        Some(entity) => *entity,
+        None => {
+            info!("no ground tile for {} at {:?}", layer.name(), cube);
+            return;
+        }
+    };
+    let mut layers = tile_query
+        .get(entity)
+        .ok()
+        .and_then(|(_, layers)| layers.copied())
+        .unwrap_or_default();
+    layers.set(layer, tile_type);
+    commands.entity(entity).insert(layers);
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn tilemap_egui_ui_system(
     mut egui_context: ResMut<EguiContext>,
-    query: Query<(Entity, &HexTileCoord, &HexTileAppearance)>,
+    query: Query<(&HexTileCoord, &HexTileAppearance, Option<&HexTileLayers>)>,
+    marker_query: Query<&MapMarker>,
+    chunks: Res<HexChunks>,
+    children_query: Query<&Children, With<HexChunk>>,
     mut interaction_state: ResMut<InteractionState>,
+    mut layer_visibility: ResMut<LayerVisibility>,
+    mut app_state: ResMut<State<AppState>>,
 ) {
+    let mut do_playtest = false;
     let mut do_save = false;
     let mut do_load = false;
     let mut do_clear = false;
@@ -44,6 +151,7 @@ pub fn tilemap_egui_ui_system(
         do_clear = ui.button("clear").clicked();
         do_load = ui.button("load").clicked();
         do_save = ui.button("save").clicked();
+        do_playtest = ui.button("playtest").clicked();
         // ui.checkbox(&mut interaction_state.fill, "fill");
         ui.radio_value(&mut interaction_state.click_mode, ClickMode::Wall, "Wall");
         ui.radio_value(
@@ -52,6 +160,78 @@ pub fn tilemap_egui_ui_system(
             "Ground",
         );
         ui.radio_value(&mut interaction_state.click_mode, ClickMode::Water, "Water");
+        ui.radio_value(
+            &mut interaction_state.click_mode,
+            ClickMode::Select,
+            "Select",
+        );
+        ui.radio_value(&mut interaction_state.click_mode, ClickMode::Stamp, "Stamp");
+
+        ui.separator();
+        ui.label("layers");
+        for layer in HexLayer::ALL {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut interaction_state.layer, layer, layer.name());
+                // only touch the resource on actual changes, so visibility is not re-applied every frame
+                let mut visible = layer_visibility.is_visible(layer);
+                if ui.checkbox(&mut visible, "visible").changed() {
+                    *layer_visibility.get_mut(layer) = visible;
+                }
+            });
+        }
+        ui.add(
+            egui::Slider::new(
+                &mut interaction_state.layer_tile,
+                0..=terrain::num_terrain_types() - 1,
+            )
+            .text("tile"),
+        );
+        ui.radio_value(
+            &mut interaction_state.click_mode,
+            ClickMode::PaintLayer,
+            "Paint layer",
+        );
+        ui.radio_value(
+            &mut interaction_state.click_mode,
+            ClickMode::EraseLayer,
+            "Erase layer",
+        );
+
+        ui.separator();
+        ui.label("symmetry");
+        ui.horizontal(|ui| {
+            let rotation = &mut interaction_state.symmetry.rotation;
+            ui.radio_value(rotation, RotationalSymmetry::None, "1");
+            ui.radio_value(rotation, RotationalSymmetry::Two, "2");
+            ui.radio_value(rotation, RotationalSymmetry::Three, "3");
+            ui.radio_value(rotation, RotationalSymmetry::Six, "6");
+        });
+        ui.checkbox(&mut interaction_state.symmetry.mirror, "mirror");
+        ui.radio_value(
+            &mut interaction_state.click_mode,
+            ClickMode::SymmetryCenter,
+            "Set center",
+        );
+
+        ui.separator();
+        ui.label("markers");
+        for kind in MarkerKind::ALL {
+            ui.radio_value(
+                &mut interaction_state.click_mode,
+                ClickMode::Marker(kind),
+                kind.name(),
+            );
+        }
+        ui.radio_value(
+            &mut interaction_state.click_mode,
+            ClickMode::MoveMarker,
+            "Move marker",
+        );
+        ui.radio_value(
+            &mut interaction_state.click_mode,
+            ClickMode::DeleteMarker,
+            "Delete marker",
+        );
 
         // do_spawn_waypoints = ui.button("-> waypoints").clicked();
     });
@@ -66,32 +246,133 @@ pub fn tilemap_egui_ui_system(
     //     spawn_tilemap(tilemap, &mut map_query, &mut commands);
     // }
     if do_save {
-        let tilemap = io::Tilemap {
-            tiles: query
-                .iter()
-                .map(|(_entity, pos, tile)| {
-                    let axial: Hex = pos.cube.into();
-                    io::Tile {
-                        x: axial.q,
-                        y: axial.r,
-                        t: tile.tile_type,
-                    }
-                })
-                .collect(),
-        };
+        // tiles go to the chunk files. map.yaml keeps the markers and the seeds of chunks that were never
+        // generated.
+        if let Err(err) = save_chunks(&chunks, &children_query, &query) {
+            warn!("failed to save chunks: {:?}", err);
+        }
+        let mut tilemap = tilemap_snapshot(std::iter::empty(), marker_query.iter());
+        tilemap.tiles = chunks
+            .seeds
+            .iter()
+            .filter(|(chunk, _)| {
+                !chunks.loaded.contains_key(chunk) && !chunks.cache.contains_key(chunk)
+            })
+            .flat_map(|(_, tiles)| tiles.iter().cloned())
+            .collect();
         tilemap.save("map.yaml").unwrap();
     }
+    if do_playtest {
+        if let Err(err) = app_state.set(AppState::Play) {
+            warn!("failed to start playtest: {:?}", err);
+        }
+    }
     // if do_spawn_waypoints {
     //     spawn_waypoints(&query, &mut commands);
     // }
 }
 
+pub fn stamp_egui_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    query: Query<(&HexTileCoord, &HexTileAppearance, Option<&HexTileLayers>)>,
+    mut interaction_state: ResMut<InteractionState>,
+) {
+    let mut do_copy = false;
+    let mut do_save = false;
+    let mut do_refresh = interaction_state.stamp_library.is_none();
+    let mut load_stamp = None;
+
+    let state = &mut *interaction_state;
+    egui::Window::new("stamps").show(egui_context.ctx_mut(), |ui| {
+        ui.label(format!("clipboard: {} tiles", state.clipboard.tiles.len()));
+        ui.horizontal(|ui| {
+            do_copy = ui.button("copy").clicked();
+            if ui.button("deselect").clicked() {
+                state.selection = None;
+                state.selection_anchor = None;
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("rotate left").clicked() {
+                state.clipboard = state.clipboard.rotated(-1);
+            }
+            if ui.button("rotate right").clicked() {
+                state.clipboard = state.clipboard.rotated(1);
+            }
+            if ui.button("mirror").clicked() {
+                state.clipboard = state.clipboard.mirrored();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut state.stamp_name);
+            do_save = ui.button("save").clicked();
+        });
+        ui.separator();
+        if let Some(library) = &state.stamp_library {
+            for name in library {
+                if ui.button(name).clicked() {
+                    load_stamp = Some(name.clone());
+                }
+            }
+        }
+        do_refresh |= ui.button("refresh").clicked();
+    });
+
+    if do_copy {
+        if let Some((a, b)) = state.selection {
+            let tiles = query
+                .iter()
+                .map(|(coord, appearance, layers)| {
+                    (
+                        coord.cube,
+                        (appearance.tile_type, layers.copied().unwrap_or_default()),
+                    )
+                })
+                .collect::<HashMap<_, _>>();
+            state.clipboard = Stamp::from_tiles(
+                a,
+                selection_cubes(a, b).filter_map(|cube| {
+                    tiles
+                        .get(&cube)
+                        .map(|(tile_type, layers)| (cube, *tile_type, *layers))
+                }),
+            );
+            state.click_mode = ClickMode::Stamp;
+        }
+    }
+    if do_save && !state.stamp_name.is_empty() && !state.clipboard.is_empty() {
+        if let Err(err) = state.clipboard.save(stamp::stamp_path(&state.stamp_name)) {
+            warn!("failed to save stamp {}: {:?}", state.stamp_name, err);
+        }
+        do_refresh = true;
+    }
+    if let Some(name) = load_stamp {
+        match Stamp::load(stamp::stamp_path(&name)) {
+            Ok(loaded) => {
+                state.clipboard = loaded;
+                state.stamp_name = name;
+                state.click_mode = ClickMode::Stamp;
+            }
+            Err(err) => warn!("failed to load stamp {}: {:?}", name, err),
+        }
+    }
+    if do_refresh {
+        state.stamp_library = Some(stamp::list_stamps());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn background_on_click(
     mut commands: Commands,
     mut click_events: EventReader<ClickEvent>,
     // mut debug_lines: ResMut<DebugLines>,
     resources: Res<Resources>,
-    interaction_state: Res<InteractionState>,
+    asset_server: Res<AssetServer>,
+    mut chunks: ResMut<HexChunks>,
+    mut index: ResMut<HexTileIndex>,
+    mut interaction_state: ResMut<InteractionState>,
+    tile_query: Query<(&HexTileAppearance, Option<&HexTileLayers>)>,
+    mut marker_query: Query<(Entity, &mut MapMarker, &mut Transform)>,
     // mut map_query: MapQuery,
     // ai_inspect_query: Query<(&HexTileCoord)>,
 ) {
@@ -99,24 +380,319 @@ pub fn background_on_click(
         // let pos = pixel_to_pointy_hex(event.pos - resources.tile_size.extend(0.0) * -0.5);
         // let cube: Cube = Cube::from_odd_r(pos); // test: unnecessary trip over cube form
 
-        let pos = (event.pos.xy() - resources.tile_size * -0.5)
-            * Vec2::new(1.0 / resources.tile_size.x, 1.0 / resources.tile_size.y);
-        let cube = Cube::from_odd_r_screen(pos);
-        info!("{:?} -> {:?}", pos, cube);
+        let cube = resources.world_to_cube(event.pos);
+        debug!("{:?} -> {:?}", event.pos, cube);
 
         let tile_type = match interaction_state.click_mode {
-            ClickMode::Wall => 0,
-            ClickMode::Ground => 2,
-            ClickMode::Water => 1,
+            ClickMode::Wall => terrain::WALL,
+            ClickMode::Ground => terrain::GROUND,
+            ClickMode::Water => terrain::WATER,
+            ClickMode::Select => {
+                if let Some(anchor) = interaction_state.selection_anchor.take() {
+                    interaction_state.selection = Some((anchor, cube));
+                } else {
+                    interaction_state.selection_anchor = Some(cube);
+                    interaction_state.selection = Some((cube, cube));
+                }
+                continue;
+            }
+            ClickMode::Stamp => {
+                for (cube, tile_type, layers) in interaction_state.clipboard.placed_at(cube) {
+                    for cube in interaction_state.symmetry.images(cube) {
+                        set_tile(
+                            &mut commands,
+                            &resources,
+                            &mut chunks,
+                            &mut index,
+                            cube,
+                            tile_type,
+                            Some(layers),
+                        );
+                    }
+                }
+                continue;
+            }
+            ClickMode::SymmetryCenter => {
+                interaction_state.symmetry.center = cube;
+                continue;
+            }
+            ClickMode::Marker(kind) => {
+                for cube in interaction_state.symmetry.images(cube) {
+                    let has_waypoint = index
+                        .tiles
+                        .get(&cube)
+                        .and_then(|entity| tile_query.get(*entity).ok())
+                        .is_some_and(|(appearance, _)| terrain_info(appearance.tile_type).walkable);
+                    if kind == MarkerKind::Waypoint && has_waypoint {
+                        // tile already has an automatic waypoint
+                        info!("not placing waypoint marker on {:?}", cube);
+                        continue;
+                    }
+                    // one marker per hex: a different kind replaces the old one
+                    let existing = marker_query
+                        .iter()
+                        .find(|(_, marker, _)| marker.cube == cube)
+                        .map(|(entity, marker, _)| (entity, marker.kind));
+                    match existing {
+                        Some((_, existing_kind)) if existing_kind == kind => continue,
+                        Some((entity, _)) => commands.entity(entity).despawn_recursive(),
+                        None => (),
+                    }
+                    marker::spawn_marker(&mut commands, &asset_server, &resources, kind, cube);
+                }
+                continue;
+            }
+            ClickMode::MoveMarker => {
+                if let Some(entity) = interaction_state.moving_marker.take() {
+                    let Ok(kind) = marker_query.get(entity).map(|(_, marker, _)| marker.kind)
+                    else {
+                        continue;
+                    };
+                    // same rules as placing: one marker per hex, a different kind gets replaced
+                    let existing = marker_query
+                        .iter()
+                        .find(|(other, marker, _)| *other != entity && marker.cube == cube)
+                        .map(|(other, marker, _)| (other, marker.kind));
+                    match existing {
+                        Some((_, existing_kind)) if existing_kind == kind => {
+                            info!("hex {:?} already has a {:?} marker", cube, kind);
+                            continue;
+                        }
+                        Some((other, _)) => commands.entity(other).despawn_recursive(),
+                        None => (),
+                    }
+                    if let Ok((_, mut marker, mut transform)) = marker_query.get_mut(entity) {
+                        marker.cube = cube;
+                        transform.translation = marker::marker_translation(&resources, cube);
+                    }
+                } else {
+                    interaction_state.moving_marker = marker_query
+                        .iter()
+                        .find(|(_, marker, _)| marker.cube == cube)
+                        .map(|(entity, _, _)| entity);
+                }
+                continue;
+            }
+            ClickMode::PaintLayer | ClickMode::EraseLayer
+                if interaction_state.layer != HexLayer::Ground =>
+            {
+                let tile_type = if interaction_state.click_mode == ClickMode::PaintLayer {
+                    Some(interaction_state.layer_tile)
+                } else {
+                    None
+                };
+                for cube in interaction_state.symmetry.images(cube) {
+                    set_layer_tile(
+                        &mut commands,
+                        &index,
+                        &tile_query,
+                        cube,
+                        interaction_state.layer,
+                        tile_type,
+                    );
+                }
+                continue;
+            }
+            ClickMode::PaintLayer => interaction_state.layer_tile,
+            ClickMode::EraseLayer => {
+                info!("the ground layer cannot be erased");
+                continue;
+            }
+            ClickMode::DeleteMarker => {
+                let cubes = interaction_state.symmetry.images(cube);
+                for (entity, marker, _) in marker_query.iter() {
+                    if cubes.contains(&marker.cube) {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                }
+                continue;
+            }
         };
 
-        commands
-            .entity(resources.base_entity)
-            .with_children(|commands| {
-                commands
-                    .spawn()
-                    .insert(HexTileCoord { cube })
-                    .insert(HexTileAppearance { tile_type });
-            });
+        for cube in interaction_state.symmetry.images(cube) {
+            set_tile(
+                &mut commands,
+                &resources,
+                &mut chunks,
+                &mut index,
+                cube,
+                tile_type,
+                None,
+            );
+        }
+    }
+}
+
+// arrow keys move the camera around the (streamed) map
+pub fn editor_camera_pan_system(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut query: Query<&mut Transform, With<Camera2d>>,
+) {
+    let mut dir = Vec3::ZERO;
+    if keyboard_input.pressed(KeyCode::Left) {
+        dir.x -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::Right) {
+        dir.x += 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::Down) {
+        dir.y -= 1.0;
     }
+    if keyboard_input.pressed(KeyCode::Up) {
+        dir.y += 1.0;
+    }
+    for mut transform in query.iter_mut() {
+        transform.translation += dir * tune::CAMERA_PAN_SPEED * time.delta_seconds();
+    }
+}
+
+pub fn editor_debug_draw_system(
+    mut debug_lines: ResMut<DebugLines>,
+    resources: Res<Resources>,
+    interaction_state: Res<InteractionState>,
+    pointer: Res<PrimaryPointerPos>,
+    marker_query: Query<&MapMarker>,
+) {
+    if let Some((a, b)) = interaction_state.selection {
+        for cube in selection_cubes(a, b) {
+            debug_draw_hex(
+                &mut debug_lines,
+                resources.cube_to_world(cube),
+                resources.tile_size,
+                None,
+            );
+        }
+    }
+    // preview where the clipboard would be stamped
+    if interaction_state.click_mode == ClickMode::Stamp {
+        let origin = resources.world_to_cube(pointer.pos);
+        for (cube, _, _) in interaction_state.clipboard.placed_at(origin) {
+            for cube in interaction_state.symmetry.images(cube) {
+                debug_draw_hex(
+                    &mut debug_lines,
+                    resources.cube_to_world(cube),
+                    resources.tile_size,
+                    None,
+                );
+            }
+        }
+    }
+    if let Some(marker) = interaction_state
+        .moving_marker
+        .and_then(|entity| marker_query.get(entity).ok())
+    {
+        debug_draw_hex(
+            &mut debug_lines,
+            resources.cube_to_world(marker.cube),
+            resources.tile_size,
+            None,
+        );
+    }
+    if interaction_state.symmetry.is_active() {
+        debug_draw_cross(
+            &mut debug_lines,
+            resources.cube_to_world(interaction_state.symmetry.center),
+            None,
+        );
+    }
+}
+
+// outline the hex under the pointer and show what is there
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn editor_hover_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut debug_lines: ResMut<DebugLines>,
+    resources: Res<Resources>,
+    pointer: Res<PrimaryPointerPos>,
+    index: Res<HexTileIndex>,
+    tile_query: Query<(&HexTileAppearance, Option<&HexTileLayers>)>,
+    entity_query: Query<(
+        &Transform,
+        AnyOf<(&InputTarget, &Zappable, &Medikit, &MapMarker, &Waypoint)>,
+    )>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) {
+    let cube = resources.world_to_cube(pointer.pos);
+    debug_draw_hex_colored(
+        &mut debug_lines,
+        resources.cube_to_world(cube),
+        resources.tile_size,
+        None,
+        Color::YELLOW,
+    );
+
+    let (camera, camera_transform) = match camera_query.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let screen_pos = match (
+        camera.world_to_viewport(camera_transform, pointer.pos),
+        camera.logical_viewport_size(),
+    ) {
+        // viewport coords start at the bottom, egui at the top
+        (Some(pos), Some(size)) => egui::pos2(pos.x, size.y - pos.y),
+        _ => return,
+    };
+
+    let axial: Hex = cube.into();
+    let odd_r = cube.to_odd_r();
+    let tile = index
+        .tiles
+        .get(&cube)
+        .and_then(|entity| tile_query.get(*entity).ok())
+        .map(|(appearance, layers)| (appearance.tile_type, layers.copied().unwrap_or_default()));
+
+    let entities = entity_query
+        .iter()
+        .filter(|(transform, _)| resources.world_to_cube(transform.translation) == cube)
+        .map(|(_, (player, ferris, medikit, marker, waypoint))| {
+            if player.is_some() {
+                "player".to_string()
+            } else if ferris.is_some() {
+                "ferris".to_string()
+            } else if medikit.is_some() {
+                "medikit".to_string()
+            } else if let Some(marker) = marker {
+                format!("{} marker", marker.kind.name())
+            } else if waypoint.is_some() {
+                "waypoint".to_string()
+            } else {
+                "?".to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    egui::Area::new("tile info")
+        .fixed_pos(screen_pos + egui::vec2(16.0, 16.0))
+        .interactable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(format!("cube: {} {} {}", cube.x, cube.y, cube.z));
+                ui.label(format!("axial: {} {}", axial.q, axial.r));
+                ui.label(format!("odd-r: {} {}", odd_r.x, odd_r.y));
+                match tile {
+                    Some((tile_type, layers)) => {
+                        let info = terrain_info(tile_type);
+                        ui.label(format!("tile: {} ({})", info.name, tile_type));
+                        ui.label(if info.walkable {
+                            "walkable"
+                        } else {
+                            "not walkable"
+                        });
+                        for layer in [HexLayer::Decoration, HexLayer::Overlay] {
+                            if let Some(tile_type) = layers.get(layer) {
+                                ui.label(format!("{}: {}", layer.name(), tile_type));
+                            }
+                        }
+                    }
+                    None => {
+                        ui.label("no tile");
+                    }
+                }
+                for entity in entities.iter() {
+                    ui.label(entity);
+                }
+            });
+        });
 }
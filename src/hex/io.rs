@@ -3,17 +3,33 @@ use std::{fs::File, path::Path};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+use super::marker::MarkerKind;
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Tile {
     pub x: i32,
     pub y: i32,
     pub t: usize,
+    // decoration / overlay layer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub d: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub o: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Marker {
+    pub x: i32,
+    pub y: i32,
+    pub kind: MarkerKind,
 }
 
 #[derive(Serialize, Deserialize)]
 
 pub struct Tilemap {
     pub tiles: Vec<Tile>,
+    #[serde(default)]
+    pub markers: Vec<Marker>,
 }
 
 impl Tilemap {
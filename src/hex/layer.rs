@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use super::tileset::Tileset;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HexLayer {
+    // the actual terrain (HexTileAppearance)
+    #[default]
+    Ground,
+    // props on top of the ground. Purely cosmetic, they never block movement.
+    Decoration,
+    // transient stuff like blood or scorch marks
+    Overlay,
+}
+
+impl HexLayer {
+    pub const ALL: [HexLayer; 3] = [HexLayer::Ground, HexLayer::Decoration, HexLayer::Overlay];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HexLayer::Ground => "ground",
+            HexLayer::Decoration => "decoration",
+            HexLayer::Overlay => "overlay",
+        }
+    }
+
+    // z offset relative to the ground sprite (must stay below markers and actors)
+    fn z(self) -> f32 {
+        match self {
+            HexLayer::Ground => 0.0,
+            HexLayer::Decoration => 0.1,
+            HexLayer::Overlay => 0.2,
+        }
+    }
+}
+
+// optional upper layers of a tile. The ground layer is the HexTileAppearance of the same entity.
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HexTileLayers {
+    pub decoration: Option<usize>,
+    pub overlay: Option<usize>,
+}
+
+impl HexTileLayers {
+    pub fn get(&self, layer: HexLayer) -> Option<usize> {
+        match layer {
+            HexLayer::Ground => None,
+            HexLayer::Decoration => self.decoration,
+            HexLayer::Overlay => self.overlay,
+        }
+    }
+    pub fn set(&mut self, layer: HexLayer, tile_type: Option<usize>) {
+        match layer {
+            HexLayer::Ground => (),
+            HexLayer::Decoration => self.decoration = tile_type,
+            HexLayer::Overlay => self.overlay = tile_type,
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.decoration.is_none() && self.overlay.is_none()
+    }
+}
+
+// sprite of an upper layer, child of the tile entity. There is no dedicated art for the upper layers yet: they
+// use the terrain tiles of the tileset (same atlas index as the tile type) as placeholders.
+#[derive(Component)]
+pub struct HexLayerSprite(pub HexLayer);
+
+pub struct LayerVisibility {
+    pub ground: bool,
+    pub decoration: bool,
+    pub overlay: bool,
+}
+
+impl Default for LayerVisibility {
+    fn default() -> Self {
+        Self {
+            ground: true,
+            decoration: true,
+            overlay: true,
+        }
+    }
+}
+
+impl LayerVisibility {
+    pub fn get_mut(&mut self, layer: HexLayer) -> &mut bool {
+        match layer {
+            HexLayer::Ground => &mut self.ground,
+            HexLayer::Decoration => &mut self.decoration,
+            HexLayer::Overlay => &mut self.overlay,
+        }
+    }
+    pub fn is_visible(&self, layer: HexLayer) -> bool {
+        match layer {
+            HexLayer::Ground => self.ground,
+            HexLayer::Decoration => self.decoration,
+            HexLayer::Overlay => self.overlay,
+        }
+    }
+}
+
+pub fn spawn_layer_sprites_system(
+    mut commands: Commands,
+    tileset: Res<Tileset>,
+    visibility: Res<LayerVisibility>,
+    query: Query<(Entity, &HexTileLayers, Option<&Children>), Changed<HexTileLayers>>,
+    mut sprite_query: Query<(&HexLayerSprite, &mut TextureAtlasSprite)>,
+) {
+    for (entity, layers, children) in query.iter() {
+        for layer in [HexLayer::Decoration, HexLayer::Overlay] {
+            let existing = children.and_then(|children| {
+                children.iter().copied().find(|child| {
+                    sprite_query
+                        .get(*child)
+                        .is_ok_and(|(sprite, _)| sprite.0 == layer)
+                })
+            });
+            match (layers.get(layer), existing) {
+                (Some(tile_type), Some(child)) => {
+                    if let Ok((_, mut sprite)) = sprite_query.get_mut(child) {
+                        sprite.index = tileset.atlas_index(tile_type);
+                    }
+                }
+                (Some(tile_type), None) => {
+                    commands.entity(entity).with_children(|commands| {
+                        commands
+                            .spawn_bundle(SpriteSheetBundle {
+                                texture_atlas: tileset.atlas.clone(),
+                                transform: Transform::from_translation(Vec3::Z * layer.z()),
+                                sprite: TextureAtlasSprite {
+                                    index: tileset.atlas_index(tile_type),
+                                    ..Default::default()
+                                },
+                                visibility: Visibility {
+                                    is_visible: visibility.is_visible(layer),
+                                },
+                                ..Default::default()
+                            })
+                            .insert(HexLayerSprite(layer));
+                    });
+                }
+                (None, Some(child)) => commands.entity(child).despawn_recursive(),
+                (None, None) => (),
+            }
+        }
+    }
+}
+
+// colour of the ground. The renderer backends hide the ground by making it transparent instead of through
+// Visibility, because in bevy 0.8 an invisible parent also hides all its children, i.e. the upper layer sprites
+// below the tiles (and the tiles below the chunks).
+pub fn ground_color(visible: bool) -> Color {
+    if visible {
+        Color::WHITE
+    } else {
+        Color::NONE
+    }
+}
+
+// follow animation frames / atlas changes of the tileset
+pub fn update_layer_sprites_system(
+    tileset: Res<Tileset>,
+    tile_query: Query<&HexTileLayers>,
+    mut sprite_query: Query<(
+        &HexLayerSprite,
+        &Parent,
+        &mut TextureAtlasSprite,
+        &mut Handle<TextureAtlas>,
+    )>,
+) {
+    if !tileset.is_changed() {
+        return;
+    }
+    for (HexLayerSprite(layer), parent, mut sprite, mut atlas) in sprite_query.iter_mut() {
+        if let Some(tile_type) = tile_query
+            .get(parent.get())
+            .ok()
+            .and_then(|layers| layers.get(*layer))
+        {
+            sprite.index = tileset.atlas_index(tile_type);
+        }
+        if *atlas != tileset.atlas {
+            *atlas = tileset.atlas.clone();
+        }
+    }
+}
+
+pub fn layer_visibility_system(
+    layer_visibility: Res<LayerVisibility>,
+    mut query: Query<(&mut Visibility, &HexLayerSprite)>,
+) {
+    if !layer_visibility.is_changed() {
+        return;
+    }
+    for (mut visibility, HexLayerSprite(layer)) in query.iter_mut() {
+        visibility.is_visible = layer_visibility.is_visible(*layer);
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy_aseprite::{anim::AsepriteAnimation, AsepriteBundle};
+use serde::{Deserialize, Serialize};
+
+use crate::{path, sprites, state::AppState};
+
+use super::{tilemap::Resources, Cube};
+
+// non-tile stuff that designers can place in the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MarkerKind {
+    PlayerSpawn,
+    FerrisSpawn,
+    MedikitSpawn,
+    // additional waypoint for places that are not covered by the automatic per-tile waypoints (e.g. water)
+    Waypoint,
+}
+
+impl MarkerKind {
+    pub const ALL: [MarkerKind; 4] = [
+        MarkerKind::PlayerSpawn,
+        MarkerKind::FerrisSpawn,
+        MarkerKind::MedikitSpawn,
+        MarkerKind::Waypoint,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MarkerKind::PlayerSpawn => "player spawn",
+            MarkerKind::FerrisSpawn => "ferris spawn",
+            MarkerKind::MedikitSpawn => "medikit spawn",
+            MarkerKind::Waypoint => "waypoint",
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct MapMarker {
+    pub kind: MarkerKind,
+    pub cube: Cube,
+}
+
+mod tune {
+    // markers are drawn below the actual actors
+    pub const MARKER_Z: f32 = 4.0;
+}
+
+pub fn marker_translation(resources: &Resources, cube: Cube) -> Vec3 {
+    resources.cube_to_world(cube) + Vec3::Z * tune::MARKER_Z
+}
+
+pub fn spawn_marker(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    resources: &Resources,
+    kind: MarkerKind,
+    cube: Cube,
+) -> Entity {
+    let (aseprite, animation) = match kind {
+        MarkerKind::PlayerSpawn | MarkerKind::FerrisSpawn => (
+            asset_server.load(sprites::Ferris::PATH),
+            AsepriteAnimation::from(sprites::Ferris::tags::STAND),
+        ),
+        MarkerKind::MedikitSpawn => (asset_server.load(sprites::Medikit::PATH), default()),
+        MarkerKind::Waypoint => (asset_server.load(sprites::Pointer::PATH), default()),
+    };
+    let mut entity_commands = commands.spawn_bundle(AsepriteBundle {
+        aseprite,
+        animation,
+        transform: Transform::from_translation(marker_translation(resources, cube)),
+        ..Default::default()
+    });
+    entity_commands.insert(MapMarker { kind, cube });
+    if kind == MarkerKind::Waypoint {
+        entity_commands.insert(path::Waypoint);
+    }
+    entity_commands.id()
+}
+
+// make markers translucent, so they can be told apart from the real thing
+pub fn marker_tint_system(
+    mut query: Query<&mut TextureAtlasSprite, (With<MapMarker>, Added<TextureAtlasSprite>)>,
+) {
+    for mut sprite in query.iter_mut() {
+        sprite.color = Color::rgba(1.0, 1.0, 1.0, 0.5);
+    }
+}
+
+// markers are only shown while editing
+pub fn marker_visibility_system(
+    state: Res<State<AppState>>,
+    mut query: Query<&mut Visibility, With<MapMarker>>,
+) {
+    let is_visible = *state.current() == AppState::Editor;
+    for mut visibility in query.iter_mut() {
+        if visibility.is_visible != is_visible {
+            visibility.is_visible = is_visible;
+        }
+    }
+}
+
+// positions of all markers of kind (at z = 0, like waypoints)
+pub fn marker_positions(query: &Query<(&MapMarker, &Transform)>, kind: MarkerKind) -> Vec<Vec3> {
+    query
+        .iter()
+        .filter(|(marker, _)| marker.kind == kind)
+        .map(|(_, transform)| transform.translation.truncate().extend(0.0))
+        .collect()
+}
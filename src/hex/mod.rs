@@ -1,9 +1,21 @@
 use bevy::{prelude::Vec2, reflect::Reflect};
 use num_traits::Num;
 
+pub mod autotile;
+pub mod chunk;
+pub mod chunk_mesh;
+pub mod collision;
+pub mod destructible;
 pub mod editor;
 pub mod io;
+pub mod layer;
+pub mod marker;
+pub mod sprite;
+pub mod stamp;
+pub mod symmetry;
+pub mod terrain;
 pub mod tilemap;
+pub mod tileset;
 pub mod wavefunction;
 
 // mostly based on https://www.redblobgames.com/grids/hexagons/
@@ -21,6 +33,25 @@ impl Cube {
     pub fn zero() -> Cube {
         Cube::default()
     }
+    // number of steps between two hexes
+    pub fn distance(self, other: Cube) -> i32 {
+        ((self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()) / 2
+    }
+    // the hexes at distance radius, going around once
+    pub fn ring(self, radius: i32) -> Vec<Cube> {
+        if radius == 0 {
+            return vec![self];
+        }
+        let mut cube = self + CUBE_DIRECTIONS[4] * radius;
+        let mut ring = Vec::new();
+        for dir in CUBE_DIRECTIONS {
+            for _ in 0..radius {
+                ring.push(cube);
+                cube += dir;
+            }
+        }
+        ring
+    }
     pub fn to_odd_r_screen(self) -> Vec2 {
         // convert to odd-r coordinates, but already shifted to on screen rendering:
         //  - row height is consolidated to 0.75
@@ -62,6 +93,35 @@ impl Cube {
         Cube { x, y, z }
     }
 
+    // rotations / reflections around the origin. Directions are meant as seen on screen (i.e. rows going
+    // upwards), so they are the other way round compared to redblobgames.
+    pub fn rotate_ccw(self) -> Cube {
+        Cube::new(-self.z, -self.x, -self.y)
+    }
+
+    pub fn rotate_cw(self) -> Cube {
+        Cube::new(-self.y, -self.z, -self.x)
+    }
+
+    // rotate by steps * 60 degrees, positive steps are clockwise
+    pub fn rotate(self, steps: i32) -> Cube {
+        let mut c = self;
+        for _ in 0..steps.rem_euclid(6) {
+            c = c.rotate_cw();
+        }
+        c
+    }
+
+    // mirror left <-> right (rows stay the same)
+    pub fn mirror_x(self) -> Cube {
+        Cube::new(self.y, self.x, self.z)
+    }
+
+    // mirror up <-> down (rows are flipped)
+    pub fn mirror_y(self) -> Cube {
+        Cube::new(-self.y, -self.x, -self.z)
+    }
+
     // let column_width = 18.0f32;
     // let column_half_width = column_width / 2.0;
 
@@ -280,3 +340,52 @@ impl Iterator for CubeLinedraw {
 pub mod prelude {
     pub use super::{Cube, Hex};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // all hexes up to distance 3 around the origin
+    fn hexes() -> Vec<Cube> {
+        (-3..=3)
+            .flat_map(|x| (-3..=3).map(move |y| Cube::new(x, y, -x - y)))
+            .filter(|c| c.z.abs() <= 3)
+            .collect()
+    }
+
+    fn radius(c: Cube) -> i32 {
+        c.x.abs().max(c.y.abs()).max(c.z.abs())
+    }
+
+    #[test]
+    fn rotate() {
+        for cube in hexes() {
+            let mut c = cube;
+            for _ in 0..6 {
+                c = c.rotate_cw();
+                assert_eq!(c.x + c.y + c.z, 0, "{:?}", c);
+                assert_eq!(radius(c), radius(cube));
+            }
+            assert_eq!(c, cube);
+            assert_eq!(cube.rotate_cw().rotate_ccw(), cube);
+            assert_eq!(cube.rotate_ccw().rotate_cw(), cube);
+            assert_eq!(cube.rotate(1), cube.rotate_cw());
+            assert_eq!(cube.rotate(-1), cube.rotate_ccw());
+            assert_eq!(cube.rotate(6), cube);
+        }
+    }
+
+    #[test]
+    fn mirror() {
+        for cube in hexes() {
+            for m in [cube.mirror_x(), cube.mirror_y()] {
+                assert_eq!(m.x + m.y + m.z, 0, "{:?}", m);
+            }
+            assert_eq!(cube.mirror_x().mirror_x(), cube);
+            assert_eq!(cube.mirror_y().mirror_y(), cube);
+            // mirror_x keeps the row, mirror_y flips it
+            assert_eq!(cube.mirror_x().z, cube.z);
+            assert_eq!(cube.mirror_y().z, -cube.z);
+        }
+    }
+}
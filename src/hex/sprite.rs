@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+
+use super::{
+    autotile::HexTileAutotile,
+    layer::{ground_color, LayerVisibility},
+    tilemap::{HexTileAppearance, HexTileCoord},
+    tileset::Tileset,
+};
+
+// renderer backend that uses one sprite per tile. Simple, but too many entities for big maps.
+#[allow(clippy::type_complexity)]
+fn spawn_sprites_system(
+    mut commands: Commands,
+    tileset: Res<Tileset>,
+    layer_visibility: Res<LayerVisibility>,
+    query: Query<(Entity, &HexTileAppearance, &HexTileAutotile), Added<HexTileAppearance>>,
+    mut sprite_query: Query<
+        (
+            ChangeTrackers<HexTileAppearance>,
+            ChangeTrackers<HexTileAutotile>,
+            &HexTileAppearance,
+            &HexTileAutotile,
+            &mut TextureAtlasSprite,
+            &mut Handle<TextureAtlas>,
+        ),
+        With<HexTileCoord>,
+    >,
+) {
+    for (entity, apperance, autotile) in query.iter() {
+        // transform and visibility are already set up by the tilemap
+        commands
+            .entity(entity)
+            .insert(tileset.atlas.clone())
+            .insert(TextureAtlasSprite {
+                index: tileset.tile_index(apperance.tile_type, autotile.mask),
+                color: ground_color(layer_visibility.ground),
+                ..Default::default()
+            });
+    }
+
+    // animation frames / atlas change for all tiles at once
+    let tileset_changed = tileset.is_changed();
+    for (tracker, autotile_tracker, appearance, autotile, mut sprite, mut atlas) in
+        sprite_query.iter_mut()
+    {
+        if !tileset_changed && !tracker.is_changed() && !autotile_tracker.is_changed() {
+            continue;
+        }
+        sprite.index = tileset.tile_index(appearance.tile_type, autotile.mask);
+        if *atlas != tileset.atlas {
+            *atlas = tileset.atlas.clone();
+        }
+    }
+}
+
+fn ground_visibility_system(
+    layer_visibility: Res<LayerVisibility>,
+    mut query: Query<&mut TextureAtlasSprite, With<HexTileAppearance>>,
+) {
+    if !layer_visibility.is_changed() {
+        return;
+    }
+    for mut sprite in query.iter_mut() {
+        sprite.color = ground_color(layer_visibility.ground);
+    }
+}
+
+pub struct HexSpriteRendererPlugin;
+
+impl Plugin for HexSpriteRendererPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_sprites_system)
+            .add_system(ground_visibility_system);
+    }
+}
@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{io, layer::HexTileLayers, Cube, Hex};
+
+pub const STAMP_DIR: &str = "stamps";
+
+// a piece of map (e.g. a room or obstacle) that can be pasted into the map. Tile positions are relative to
+// the stamp origin, which is also the center of rotation / mirroring. Each tile has its ground tile type and the
+// upper layers on top of it.
+#[derive(Default, Clone)]
+pub struct Stamp {
+    pub tiles: Vec<(Cube, usize, HexTileLayers)>,
+}
+
+impl Stamp {
+    pub fn from_tiles<I: IntoIterator<Item = (Cube, usize, HexTileLayers)>>(
+        origin: Cube,
+        tiles: I,
+    ) -> Self {
+        Stamp {
+            tiles: tiles
+                .into_iter()
+                .map(|(cube, tile_type, layers)| (cube - origin, tile_type, layers))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    // positive steps rotate clockwise
+    pub fn rotated(&self, steps: i32) -> Self {
+        Stamp {
+            tiles: self
+                .tiles
+                .iter()
+                .map(|(cube, tile_type, layers)| (cube.rotate(steps), *tile_type, *layers))
+                .collect(),
+        }
+    }
+
+    pub fn mirrored(&self) -> Self {
+        Stamp {
+            tiles: self
+                .tiles
+                .iter()
+                .map(|(cube, tile_type, layers)| (cube.mirror_x(), *tile_type, *layers))
+                .collect(),
+        }
+    }
+
+    // absolute tile positions when the stamp is placed at origin
+    pub fn placed_at(
+        &self,
+        origin: Cube,
+    ) -> impl Iterator<Item = (Cube, usize, HexTileLayers)> + '_ {
+        self.tiles
+            .iter()
+            .map(move |(cube, tile_type, layers)| (*cube + origin, *tile_type, *layers))
+    }
+
+    // stamps use the same file format as the map (with axial coords relative to the origin)
+    pub fn load<P: AsRef<Path>>(filename: P) -> Result<Self> {
+        let tilemap = io::Tilemap::load(filename)?;
+        Ok(Stamp {
+            tiles: tilemap
+                .tiles
+                .iter()
+                .map(|tile| {
+                    (
+                        Hex {
+                            q: tile.x,
+                            r: tile.y,
+                        }
+                        .into(),
+                        tile.t,
+                        HexTileLayers {
+                            decoration: tile.d,
+                            overlay: tile.o,
+                        },
+                    )
+                })
+                .collect(),
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, filename: P) -> Result<()> {
+        if let Some(dir) = filename.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tilemap = io::Tilemap {
+            tiles: self
+                .tiles
+                .iter()
+                .map(|(cube, tile_type, layers)| {
+                    let axial: Hex = (*cube).into();
+                    io::Tile {
+                        x: axial.q,
+                        y: axial.r,
+                        t: *tile_type,
+                        d: layers.decoration,
+                        o: layers.overlay,
+                    }
+                })
+                .collect(),
+            markers: Vec::new(),
+        };
+        tilemap.save(filename)
+    }
+}
+
+pub fn stamp_path(name: &str) -> PathBuf {
+    Path::new(STAMP_DIR).join(format!("{}.yaml", name))
+}
+
+// names of all stamps in the library directory (sorted, without extension)
+pub fn list_stamps() -> Vec<String> {
+    let mut names = std::fs::read_dir(STAMP_DIR)
+        .map(|dir| {
+            dir.filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+                .filter_map(|path| {
+                    path.file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .map(|stem| stem.to_string())
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
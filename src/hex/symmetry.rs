@@ -0,0 +1,134 @@
+use super::Cube;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RotationalSymmetry {
+    #[default]
+    None,
+    Two,
+    Three,
+    Six,
+}
+
+impl RotationalSymmetry {
+    pub fn fold(self) -> i32 {
+        match self {
+            RotationalSymmetry::None => 1,
+            RotationalSymmetry::Two => 2,
+            RotationalSymmetry::Three => 3,
+            RotationalSymmetry::Six => 6,
+        }
+    }
+}
+
+// symmetry used for painting competitive maps: every position is replicated by rotating around center (and
+// optionally mirroring left <-> right through center)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Symmetry {
+    pub rotation: RotationalSymmetry,
+    pub mirror: bool,
+    pub center: Cube,
+}
+
+impl Symmetry {
+    pub fn is_active(&self) -> bool {
+        self.rotation != RotationalSymmetry::None || self.mirror
+    }
+
+    // all images of cube (including cube itself). Positions that map onto themselves are only returned once.
+    pub fn images(&self, cube: Cube) -> Vec<Cube> {
+        let fold = self.rotation.fold();
+        let step = 6 / fold;
+        let rel = cube - self.center;
+
+        let mut res = Vec::with_capacity(fold as usize * 2);
+        for i in 0..fold {
+            let rotated = rel.rotate(i * step);
+            res.push(rotated + self.center);
+            if self.mirror {
+                res.push(rotated.mirror_x() + self.center);
+            }
+        }
+        res.sort_by_key(|c| (c.x, c.z));
+        res.dedup();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const CENTER: Cube = Cube { x: 2, y: -5, z: 3 };
+
+    fn symmetry(rotation: RotationalSymmetry, mirror: bool) -> Symmetry {
+        Symmetry {
+            rotation,
+            mirror,
+            center: CENTER,
+        }
+    }
+
+    fn all() -> Vec<Symmetry> {
+        [
+            RotationalSymmetry::None,
+            RotationalSymmetry::Two,
+            RotationalSymmetry::Three,
+            RotationalSymmetry::Six,
+        ]
+        .into_iter()
+        .flat_map(|rotation| [symmetry(rotation, false), symmetry(rotation, true)])
+        .collect()
+    }
+
+    #[test]
+    fn image_counts() {
+        // off every mirror axis
+        let cube = CENTER + Cube::new(2, -3, 1);
+        for symmetry in all() {
+            let fold = symmetry.rotation.fold() as usize;
+            let expected = if symmetry.mirror { 2 * fold } else { fold };
+            assert_eq!(symmetry.images(cube).len(), expected, "{:?}", symmetry);
+        }
+    }
+
+    #[test]
+    fn center_and_axes_are_not_duplicated() {
+        for symmetry in all() {
+            assert_eq!(symmetry.images(CENTER), vec![CENTER], "{:?}", symmetry);
+            // on the axis of mirror_x: the mirror image is the hex itself
+            let on_axis = CENTER + Cube::new(1, 1, -2);
+            let fold = symmetry.rotation.fold() as usize;
+            assert_eq!(symmetry.images(on_axis).len(), fold, "{:?}", symmetry);
+        }
+    }
+
+    // distance from the origin
+    fn radius(c: Cube) -> i32 {
+        c.x.abs().max(c.y.abs()).max(c.z.abs())
+    }
+
+    #[test]
+    fn images_keep_distance_and_are_closed() {
+        for symmetry in all() {
+            for x in -3..=3 {
+                for y in -3..=3 {
+                    let rel = Cube::new(x, y, -x - y);
+                    if rel.z.abs() > 3 {
+                        continue;
+                    }
+                    let cube = CENTER + rel;
+                    let images = symmetry.images(cube);
+                    assert!(images.contains(&cube));
+                    let unique = images.iter().collect::<HashSet<_>>();
+                    assert_eq!(unique.len(), images.len(), "{:?}", symmetry);
+                    for image in images.iter() {
+                        assert_eq!(radius(*image - CENTER), radius(rel), "{:?}", symmetry);
+                        // painting any image paints the same set
+                        assert_eq!(symmetry.images(*image), images, "{:?}", symmetry);
+                    }
+                }
+            }
+        }
+    }
+}
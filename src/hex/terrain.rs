@@ -0,0 +1,146 @@
+// tile types used in HexTileAppearance::tile_type (the tileset maps them to atlas indices)
+pub const WALL: usize = 0;
+pub const WATER: usize = 1;
+pub const GROUND: usize = 2;
+pub const LAVA: usize = 7;
+pub const CRACKED_WALL: usize = 8;
+pub const RUBBLE: usize = 9;
+
+mod tune {
+    // how much a point of damage per second weighs against the time it takes to cross a tile
+    pub const DAMAGE_COST: f32 = 0.1;
+}
+
+pub struct TerrainInfo {
+    pub name: &'static str,
+    pub walkable: bool,
+    // multiplier on the walk speed
+    pub speed: f32,
+    // damage to everything that stands on the tile
+    pub damage_per_second: f32,
+    // fraction of the velocity that is kept after one second (0: instant control, close to 1: ice)
+    pub slipperiness: f32,
+    // damage the tile takes before it turns into destroyed_into. 0: indestructible
+    pub hit_points: f32,
+    pub destroyed_into: usize,
+}
+
+impl TerrainInfo {
+    // relative cost of crossing a tile for path finding (1.0 for plain ground)
+    pub fn travel_cost(&self) -> f32 {
+        1.0 / self.speed.max(0.01) + self.damage_per_second * tune::DAMAGE_COST
+    }
+}
+
+const TERRAIN: [TerrainInfo; 10] = [
+    TerrainInfo {
+        name: "wall",
+        walkable: false,
+        speed: 0.0,
+        damage_per_second: 0.0,
+        slipperiness: 0.0,
+        hit_points: 30.0,
+        destroyed_into: CRACKED_WALL,
+    },
+    TerrainInfo {
+        name: "water",
+        walkable: true,
+        speed: 0.5,
+        damage_per_second: 0.0,
+        slipperiness: 0.0,
+        hit_points: 0.0,
+        destroyed_into: 0,
+    },
+    TerrainInfo {
+        name: "ground",
+        walkable: true,
+        speed: 1.0,
+        damage_per_second: 0.0,
+        slipperiness: 0.0,
+        hit_points: 0.0,
+        destroyed_into: 0,
+    },
+    TerrainInfo {
+        name: "moss",
+        walkable: true,
+        speed: 1.0,
+        damage_per_second: 0.0,
+        slipperiness: 0.3,
+        hit_points: 0.0,
+        destroyed_into: 0,
+    },
+    TerrainInfo {
+        name: "sand",
+        walkable: true,
+        speed: 0.8,
+        damage_per_second: 0.0,
+        slipperiness: 0.0,
+        hit_points: 0.0,
+        destroyed_into: 0,
+    },
+    TerrainInfo {
+        name: "rock",
+        walkable: true,
+        speed: 0.8,
+        damage_per_second: 0.0,
+        slipperiness: 0.0,
+        hit_points: 0.0,
+        destroyed_into: 0,
+    },
+    TerrainInfo {
+        name: "dirt",
+        walkable: true,
+        speed: 0.9,
+        damage_per_second: 0.0,
+        slipperiness: 0.0,
+        hit_points: 0.0,
+        destroyed_into: 0,
+    },
+    // hazard: can be crossed, but hurts
+    TerrainInfo {
+        name: "lava",
+        walkable: true,
+        speed: 0.8,
+        damage_per_second: 10.0,
+        slipperiness: 0.0,
+        hit_points: 0.0,
+        destroyed_into: 0,
+    },
+    // damage stages of walls
+    TerrainInfo {
+        name: "cracked wall",
+        walkable: false,
+        speed: 0.0,
+        damage_per_second: 0.0,
+        slipperiness: 0.0,
+        hit_points: 30.0,
+        destroyed_into: RUBBLE,
+    },
+    TerrainInfo {
+        name: "rubble",
+        walkable: true,
+        speed: 0.7,
+        damage_per_second: 0.0,
+        slipperiness: 0.0,
+        hit_points: 0.0,
+        destroyed_into: 0,
+    },
+];
+
+const UNKNOWN: TerrainInfo = TerrainInfo {
+    name: "unknown",
+    walkable: false,
+    speed: 1.0,
+    damage_per_second: 0.0,
+    slipperiness: 0.0,
+    hit_points: 0.0,
+    destroyed_into: 0,
+};
+
+pub fn num_terrain_types() -> usize {
+    TERRAIN.len()
+}
+
+pub fn terrain_info(tile_type: usize) -> &'static TerrainInfo {
+    TERRAIN.get(tile_type).unwrap_or(&UNKNOWN)
+}
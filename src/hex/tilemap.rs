@@ -2,11 +2,27 @@ use std::collections::HashMap;
 
 use bevy::prelude::*;
 
-use crate::{hex::Cube, path};
+use crate::{hex::Cube, path, state::AppState};
 
 use super::{
-    editor::{background_on_click, tilemap_egui_ui_system, InteractionState},
-    io, wavefunction, Hex,
+    autotile::{autotile_system, HexTileAutotile},
+    chunk::{self, cube_to_chunk, stream_chunks_system, HexChunks},
+    chunk_mesh::HexChunkMeshRendererPlugin,
+    destructible::{tile_damage_system, TileHitEvent},
+    editor::{
+        background_on_click, editor_camera_pan_system, editor_debug_draw_system,
+        editor_hover_system, stamp_egui_ui_system, tilemap_egui_ui_system, InteractionState,
+    },
+    io,
+    layer::{
+        layer_visibility_system, spawn_layer_sprites_system, update_layer_sprites_system,
+        HexTileLayers, LayerVisibility,
+    },
+    marker::{self, marker_tint_system, marker_visibility_system, MapMarker},
+    sprite::HexSpriteRendererPlugin,
+    terrain::terrain_info,
+    tileset::{init_tileset, Tileset, TilesetPlugin},
+    Hex,
 };
 
 #[derive(Component, Default, Reflect)]
@@ -23,31 +39,93 @@ pub struct HexTileCoord {
 
 pub struct Resources {
     pub base_entity: Entity,
-    pub texture_atlas: Handle<TextureAtlas>,
     pub tile_size: Vec2,
 }
 
+impl Resources {
+    pub fn world_to_cube(&self, pos: Vec3) -> Cube {
+        let pos = (pos.truncate() + self.tile_size * 0.5) / self.tile_size;
+        Cube::from_odd_r_screen(pos)
+    }
+    pub fn cube_to_world(&self, cube: Cube) -> Vec3 {
+        (cube.to_odd_r_screen() * self.tile_size).extend(0.0)
+    }
+}
+
 impl Default for Resources {
     fn default() -> Self {
         Self {
             base_entity: Entity::from_raw(0), // FIXME: this is set in the init_system, but I'm too lazy for Option<>
-            texture_atlas: Default::default(),
             tile_size: Default::default(),
         }
     }
 }
 
+// lookup from hex coordinate to tile entity. Code that spawns or moves tiles should register them right away
+// through insert(), the system below only catches up on changes made elsewhere.
+#[derive(Default)]
+pub struct HexTileIndex {
+    pub tiles: HashMap<Cube, Entity>,
+    cubes: HashMap<Entity, Cube>,
+}
+
+impl HexTileIndex {
+    pub fn insert(&mut self, cube: Cube, entity: Entity) {
+        if let Some(old) = self.cubes.insert(entity, cube) {
+            if old != cube && self.tiles.get(&old) == Some(&entity) {
+                self.tiles.remove(&old);
+            }
+        }
+        if let Some(replaced) = self.tiles.insert(cube, entity) {
+            if replaced != entity {
+                self.cubes.remove(&replaced);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.cubes.clear();
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(cube) = self.cubes.remove(&entity) {
+            if self.tiles.get(&cube) == Some(&entity) {
+                self.tiles.remove(&cube);
+            }
+        }
+    }
+}
+
+fn update_tile_index_system(
+    mut index: ResMut<HexTileIndex>,
+    query: Query<(Entity, &HexTileCoord), Changed<HexTileCoord>>,
+    removed: RemovedComponents<HexTileCoord>,
+) {
+    for entity in removed.iter() {
+        index.remove(entity);
+    }
+    for (entity, coord) in query.iter() {
+        index.insert(coord.cube, entity);
+    }
+}
+
 fn init_system(
     mut commands: Commands,
     mut resources: ResMut<Resources>,
+    mut chunks: ResMut<HexChunks>,
     asset_server: Res<AssetServer>,
+    mut tileset: ResMut<Tileset>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 ) {
     //
-    let texture_handle = asset_server.load("pointy_hex_tiles_18x20.png");
     resources.tile_size = Vec2::new(18.0, 20.0);
-    let texture_atlas = TextureAtlas::from_grid(texture_handle, resources.tile_size, 7, 1);
-    resources.texture_atlas = texture_atlases.add(texture_atlas);
+    init_tileset(
+        &mut tileset,
+        &asset_server,
+        &mut texture_atlases,
+        resources.tile_size,
+    );
     // commands.spawn_bundle(SpriteSheetBundle {
     //     texture_atlas: texture_atlas_handle,
     //     transform: Transform::from_translation(Vec3::new(128.0, 128.0, 0.0)),
@@ -59,62 +137,92 @@ fn init_system(
         .insert_bundle(SpatialBundle::default())
         .id();
 
+    // tiles in map.yaml are only the input for generating chunks, the actual tiles are streamed in
     if let Ok(init) = io::Tilemap::load("map.yaml") {
-        let tiles: HashMap<Cube, usize> = init
-            .tiles
-            .iter()
-            .map(|x| {
-                let axial = Hex { q: x.x, r: x.y };
-                (axial.into(), x.t)
-            })
-            .collect();
-
-        for (cube, tile_type) in wavefunction::test(&tiles) {
-            commands
-                .entity(resources.base_entity)
-                .with_children(|commands| {
-                    commands
-                        .spawn()
-                        .insert(HexTileCoord { cube })
-                        .insert(HexTileAppearance { tile_type });
-                });
+        for io::Marker { x, y, kind } in init.markers.iter() {
+            let cube = Hex { q: *x, r: *y }.into();
+            marker::spawn_marker(&mut commands, &asset_server, &resources, *kind, cube);
         }
+        chunk::init_seeds(&mut chunks, init.tiles);
     }
 }
 
-fn spawn_sprites_system(
-    mut commands: Commands,
-    resources: Res<Resources>,
-    query: Query<(Entity, &HexTileCoord, &HexTileAppearance), Added<HexTileAppearance>>,
-    mut query_changed: Query<
-        (Entity, &HexTileCoord, &HexTileAppearance, &mut Transform),
-        Changed<HexTileCoord>,
-    >,
+// tiles are children of the chunk they are in. They are registered in the index right away, so a tile spawned
+// in this frame is not spawned a second time by a later click or stamp.
+pub fn spawn_tile(
+    commands: &mut Commands,
+    resources: &Resources,
+    chunks: &mut HexChunks,
+    index: &mut HexTileIndex,
+    cube: Cube,
+    tile_type: usize,
+) -> Entity {
+    let chunk_entity = chunks.chunk_entity(commands, resources, cube_to_chunk(cube));
+    // tiles need visibility even without a sprite of their own (chunk mesh backend), otherwise it does not
+    // propagate to the layer sprites below them
+    let entity = commands
+        .spawn_bundle(SpatialBundle::from_transform(Transform::from_translation(
+            resources.cube_to_world(cube),
+        )))
+        .insert(HexTileCoord { cube })
+        .insert(HexTileAppearance { tile_type })
+        .insert(HexTileAutotile::default())
+        .id();
+    commands.entity(chunk_entity).add_child(entity);
+    index.insert(cube, entity);
+    entity
+}
+
+// spawn tiles and markers of a (loaded or snapshotted) tilemap
+pub fn spawn_tilemap(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    resources: &Resources,
+    chunks: &mut HexChunks,
+    index: &mut HexTileIndex,
+    tilemap: &io::Tilemap,
 ) {
-    for (entity, coord, apperance) in query.iter() {
-        let coord_screen = coord.cube.to_odd_r_screen() * resources.tile_size;
-        info!("coord_screen: {:?}", coord_screen);
-        let index = apperance.tile_type;
-        // commands.entity(entity).with_children(|commands| {
-        commands.entity(entity).insert_bundle(SpriteSheetBundle {
-            texture_atlas: resources.texture_atlas.clone(),
-            transform: Transform::from_translation(coord_screen.extend(0.0)),
-            sprite: TextureAtlasSprite {
-                index,
-                ..Default::default()
-            },
-            ..Default::default()
-        });
+    chunk::spawn_tiles(commands, resources, chunks, index, &tilemap.tiles);
+    for io::Marker { x, y, kind } in tilemap.markers.iter() {
+        let cube = Hex { q: *x, r: *y }.into();
+        marker::spawn_marker(commands, asset_server, resources, *kind, cube);
     }
+}
 
-    for (entity, coord, _apperance, mut transform) in query_changed.iter_mut() {
-        if query.get(entity).is_ok() {
-            continue;
-        }
+pub fn tilemap_snapshot<'a>(
+    tiles: impl Iterator<
+        Item = (
+            &'a HexTileCoord,
+            &'a HexTileAppearance,
+            Option<&'a HexTileLayers>,
+        ),
+    >,
+    markers: impl Iterator<Item = &'a MapMarker>,
+) -> io::Tilemap {
+    io::Tilemap {
+        tiles: tiles
+            .map(|(pos, tile, layers)| chunk::tile_to_io(pos, tile, layers))
+            .collect(),
+        markers: markers
+            .map(|marker| {
+                let axial: Hex = marker.cube.into();
+                io::Marker {
+                    x: axial.q,
+                    y: axial.r,
+                    kind: marker.kind,
+                }
+            })
+            .collect(),
+    }
+}
 
-        let coord_screen = coord.cube.to_odd_r_screen() * resources.tile_size;
-        transform.translation = coord_screen.extend(0.0);
-        // info!("coord_screen: {:?}", coord_screen);
+// keep tile transforms in sync with their coordinate (the renderer backends take care of the appearance)
+fn update_tile_transform_system(
+    resources: Res<Resources>,
+    mut query: Query<(&HexTileCoord, &mut Transform), Changed<HexTileCoord>>,
+) {
+    for (coord, mut transform) in query.iter_mut() {
+        transform.translation = resources.cube_to_world(coord.cube);
     }
 }
 
@@ -133,36 +241,138 @@ pub fn pixel_to_pointy_hex(p: Vec3) -> Vec2 {
     Vec2::new(major_x, major_y)
 }
 
-fn spawn_waypoints_system(
-    query: Query<(Entity, &HexTileCoord, &HexTileAppearance), Added<HexTileAppearance>>,
+// waypoint of a walkable tile
+#[derive(Component)]
+pub struct HexTileWaypoint(pub Entity);
+
+// waypoints follow the walkability of their tile (editing, destroyed walls). They live in the chunk of their
+// tile, so they go away when the chunk is unloaded.
+#[allow(clippy::type_complexity)]
+fn update_waypoints_system(
+    query: Query<
+        (
+            Entity,
+            &HexTileCoord,
+            &HexTileAppearance,
+            &Parent,
+            Option<&HexTileWaypoint>,
+        ),
+        Changed<HexTileAppearance>,
+    >,
+    mut cost_query: Query<&mut path::WaypointCost>,
     resources: Res<Resources>,
     mut commands: Commands,
 ) {
-    for (_entity, tile_pos, tile) in query.iter() {
-        if (0..2).contains(&tile.tile_type) {
-            continue;
+    for (entity, tile_pos, tile, parent, waypoint) in query.iter() {
+        let terrain = terrain_info(tile.tile_type);
+        match (terrain.walkable, waypoint) {
+            (true, None) => {
+                let waypoint = commands
+                    .spawn()
+                    .insert(path::Waypoint)
+                    .insert(path::WaypointCost(terrain.travel_cost()))
+                    .insert(Transform::from_translation(
+                        resources.cube_to_world(tile_pos.cube),
+                    ))
+                    .insert(GlobalTransform::default())
+                    .id();
+                commands.entity(parent.get()).add_child(waypoint);
+                commands.entity(entity).insert(HexTileWaypoint(waypoint));
+            }
+            (true, Some(HexTileWaypoint(waypoint))) => {
+                if let Ok(mut cost) = cost_query.get_mut(*waypoint) {
+                    if cost.0 != terrain.travel_cost() {
+                        cost.0 = terrain.travel_cost();
+                    }
+                }
+            }
+            (false, Some(HexTileWaypoint(waypoint))) => {
+                commands.entity(*waypoint).despawn_recursive();
+                commands.entity(entity).remove::<HexTileWaypoint>();
+            }
+            (false, None) => (),
         }
-        commands
-            .spawn()
-            .insert(path::Waypoint)
-            .insert(Transform::from_translation(
-                (tile_pos.cube.to_odd_r_screen() * resources.tile_size).extend(0.0),
-            ));
     }
 }
 
-pub struct HexTilemapPlugin;
+// how the ground layer is drawn. Everything else (tile data, editor, waypoints, collision, save / load) is
+// independent of this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HexRenderer {
+    // one sprite entity per tile
+    Sprites,
+    // one mesh per chunk
+    #[default]
+    ChunkMesh,
+}
+
+#[derive(Default)]
+pub struct HexTilemapPlugin {
+    pub renderer: HexRenderer,
+}
 
 impl Plugin for HexTilemapPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Resources>()
+        match self.renderer {
+            HexRenderer::Sprites => app.add_plugin(HexSpriteRendererPlugin),
+            HexRenderer::ChunkMesh => app.add_plugin(HexChunkMeshRendererPlugin),
+        };
+        app.add_plugin(TilesetPlugin)
+            .init_resource::<Resources>()
             .register_type::<HexTileAppearance>()
             .register_type::<HexTileCoord>()
+            .init_resource::<HexTileIndex>()
+            .add_event::<TileHitEvent>()
+            .init_resource::<HexChunks>()
             .init_resource::<InteractionState>()
+            .init_resource::<LayerVisibility>()
             .add_startup_system(init_system)
-            .add_system(spawn_sprites_system)
-            .add_system(spawn_waypoints_system)
-            .add_system(background_on_click)
-            .add_system(tilemap_egui_ui_system);
+            .add_system(update_tile_index_system)
+            .add_system(autotile_system.after(update_tile_index_system))
+            .add_system(stream_chunks_system)
+            .add_system(marker_tint_system)
+            .add_system(update_tile_transform_system)
+            .add_system(spawn_layer_sprites_system)
+            .add_system(update_layer_sprites_system)
+            .add_system(layer_visibility_system)
+            .add_system(update_waypoints_system)
+            .add_system(tile_damage_system)
+            .add_system(marker_visibility_system)
+            .add_system_set(
+                SystemSet::on_update(AppState::Editor)
+                    .with_system(background_on_click)
+                    .with_system(tilemap_egui_ui_system)
+                    .with_system(stamp_egui_ui_system)
+                    .with_system(editor_debug_draw_system)
+                    .with_system(editor_hover_system)
+                    .with_system(editor_camera_pan_system),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_index_follows_moves_and_removals() {
+        let mut index = HexTileIndex::default();
+        let a = Entity::from_raw(1);
+        let b = Entity::from_raw(2);
+        let c0 = Cube::new(0, 0, 0);
+        let c1 = Cube::new(1, -1, 0);
+
+        index.insert(c0, a);
+        index.insert(c1, a);
+        assert_eq!(index.tiles.get(&c0), None);
+        assert_eq!(index.tiles.get(&c1), Some(&a));
+
+        // b takes over c1, removing a must not drop it
+        index.insert(c1, b);
+        index.remove(a);
+        assert_eq!(index.tiles.get(&c1), Some(&b));
+
+        index.remove(b);
+        assert!(index.tiles.is_empty());
     }
 }
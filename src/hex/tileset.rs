@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_aseprite::Aseprite;
+
+use super::{autotile::AutotileTable, terrain::terrain_info};
+
+// static tiles, used as long as the aseprite tileset is not (or never) loaded
+pub const TILESET_PNG: &str = "pointy_hex_tiles_18x20.png";
+// the original tiles, the transition variants (edges of water, wall, ground, moss and rock), then lava and the
+// wall damage stages
+const PNG_COLUMNS: usize = 325;
+// image of each tile type in TILESET_PNG
+const PNG_TILES: [usize; 10] = [0, 1, 2, 3, 4, 5, 6, 322, 323, 324];
+// frame n is the static image of tile type n. Animated tile types have a tag named like the terrain
+// (e.g. 'water').
+pub const TILESET_ASEPRITE: &str = "hex_tiles.aseprite";
+// transition variants (see AutotileTable) that go with the tilesets above: columns of the png, frames of the
+// aseprite file. Read directly, not via the asset server.
+pub const AUTOTILE_PNG: &str = "assets/pointy_hex_tiles_18x20.autotile.yaml";
+pub const AUTOTILE_ASEPRITE: &str = "assets/hex_tiles.autotile.yaml";
+
+// texture atlas for tiles and the atlas index that is currently shown per tile type. All tiles of a type share
+// the same index, so animated tiles run in sync.
+#[derive(Default)]
+pub struct Tileset {
+    pub atlas: Handle<TextureAtlas>,
+    indices: Vec<usize>,
+    aseprite: Handle<Aseprite>,
+    // per tile type: (atlas index, duration) of each animation frame
+    animations: Vec<Option<Vec<(usize, Duration)>>>,
+    autotile: AutotileTable,
+    // aseprite tileset is in use
+    loaded: bool,
+}
+
+impl Tileset {
+    pub fn atlas_index(&self, tile_type: usize) -> usize {
+        self.indices.get(tile_type).copied().unwrap_or(tile_type)
+    }
+
+    pub fn is_animated(&self, tile_type: usize) -> bool {
+        self.animations
+            .get(tile_type)
+            .is_some_and(|frames| frames.is_some())
+    }
+
+    // atlas index including the transition to the neighbours. Transition variants are not animated.
+    pub fn tile_index(&self, tile_type: usize, mask: u8) -> usize {
+        self.autotile
+            .variant(tile_type, mask)
+            .unwrap_or_else(|| self.atlas_index(tile_type))
+    }
+}
+
+fn load_autotile_table(filename: &str) -> AutotileTable {
+    AutotileTable::load(filename).unwrap_or_else(|err| {
+        debug!("no autotile table {}: {:?}", filename, err);
+        AutotileTable::default()
+    })
+}
+
+pub fn init_tileset(
+    tileset: &mut Tileset,
+    asset_server: &AssetServer,
+    texture_atlases: &mut Assets<TextureAtlas>,
+    tile_size: Vec2,
+) {
+    let texture_handle = asset_server.load(TILESET_PNG);
+    let texture_atlas = TextureAtlas::from_grid(texture_handle, tile_size, PNG_COLUMNS, 1);
+    tileset.atlas = texture_atlases.add(texture_atlas);
+    tileset.indices = PNG_TILES.to_vec();
+    tileset.animations.clear();
+    tileset.autotile = load_autotile_table(AUTOTILE_PNG);
+    tileset.loaded = false;
+    // if this does not exist the asset server only complains once and we stick with the png
+    tileset.aseprite = asset_server.load(TILESET_ASEPRITE);
+}
+
+// switch over to the aseprite tileset once it is loaded
+fn load_tileset_system(
+    mut tileset: ResMut<Tileset>,
+    mut asset_events: EventReader<AssetEvent<Aseprite>>,
+    aseprites: Res<Assets<Aseprite>>,
+) {
+    // pick up changes when the file is reloaded
+    let modified = asset_events.iter().any(|event| match event {
+        AssetEvent::Modified { handle } => *handle == tileset.aseprite,
+        _ => false,
+    });
+    if tileset.loaded && !modified {
+        return;
+    }
+    let aseprite = match aseprites.get(&tileset.aseprite) {
+        Some(aseprite) => aseprite,
+        None => return,
+    };
+    let atlas = match aseprite.atlas() {
+        Some(atlas) => atlas.clone(),
+        None => return,
+    };
+    let indices = (0..)
+        .map_while(|frame| aseprite.atlas_index(frame))
+        .collect::<Vec<_>>();
+    let animations = (0..indices.len())
+        .map(|tile_type| {
+            let name = terrain_info(tile_type).name;
+            aseprite.tag_frames(name).map(|frames| {
+                frames
+                    .into_iter()
+                    .filter_map(|(frame, duration)| Some((aseprite.atlas_index(frame)?, duration)))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect::<Vec<_>>();
+    info!(
+        "tileset {}: {} frames, {} animated tile types",
+        TILESET_ASEPRITE,
+        indices.len(),
+        animations.iter().filter(|a| a.is_some()).count()
+    );
+    tileset.atlas = atlas;
+    tileset.indices = indices;
+    tileset.animations = animations;
+    // the atlas builder packs the frames in its own order
+    tileset.autotile =
+        load_autotile_table(AUTOTILE_ASEPRITE).map_indices(|frame| aseprite.atlas_index(frame));
+    tileset.loaded = true;
+}
+
+// frame of an animation at time t (looping)
+fn animation_frame(frames: &[(usize, Duration)], t: Duration) -> Option<usize> {
+    let total = frames
+        .iter()
+        .map(|(_, duration)| *duration)
+        .sum::<Duration>();
+    if total.is_zero() {
+        return frames.first().map(|(index, _)| *index);
+    }
+    let mut t = Duration::from_nanos((t.as_nanos() % total.as_nanos()) as u64);
+    for (index, duration) in frames {
+        if t < *duration {
+            return Some(*index);
+        }
+        t -= *duration;
+    }
+    frames.last().map(|(index, _)| *index)
+}
+
+// advance all tile animations from the shared clock
+fn animate_tileset_system(time: Res<Time>, mut tileset: ResMut<Tileset>) {
+    let t = time.time_since_startup();
+    let changed = tileset
+        .animations
+        .iter()
+        .enumerate()
+        .filter_map(|(tile_type, frames)| {
+            let index = animation_frame(frames.as_ref()?, t)?;
+            (tileset.indices.get(tile_type) != Some(&index)).then_some((tile_type, index))
+        })
+        .collect::<Vec<_>>();
+
+    // only touch the resource on actual frame changes, renderers react to change detection
+    for (tile_type, index) in changed {
+        tileset.indices[tile_type] = index;
+    }
+}
+
+pub struct TilesetPlugin;
+
+impl Plugin for TilesetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tileset>()
+            .add_system(load_tileset_system)
+            .add_system(animate_tileset_system.after(load_tileset_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::terrain::{self, num_terrain_types};
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn animation_frame_loops() {
+        let frames = [(10, ms(100)), (11, ms(200)), (12, ms(100))];
+        assert_eq!(animation_frame(&frames, ms(0)), Some(10));
+        assert_eq!(animation_frame(&frames, ms(99)), Some(10));
+        assert_eq!(animation_frame(&frames, ms(100)), Some(11));
+        assert_eq!(animation_frame(&frames, ms(299)), Some(11));
+        assert_eq!(animation_frame(&frames, ms(300)), Some(12));
+        // next round
+        assert_eq!(animation_frame(&frames, ms(400)), Some(10));
+        assert_eq!(animation_frame(&frames, ms(4 * 400 + 150)), Some(11));
+    }
+
+    #[test]
+    fn animation_frame_degenerate() {
+        assert_eq!(animation_frame(&[], ms(100)), None);
+        // zero length frames stick to the first one
+        assert_eq!(animation_frame(&[(3, ms(0)), (4, ms(0))], ms(100)), Some(3));
+    }
+
+    // the png tileset has an image for every tile type
+    #[test]
+    fn png_tiles() {
+        assert_eq!(PNG_TILES.len(), num_terrain_types());
+        assert!(PNG_TILES.iter().all(|column| *column < PNG_COLUMNS));
+    }
+
+    // the aseprite tileset has a frame for every tile type and a tag for the animated ones
+    #[test]
+    fn aseprite_tileset() {
+        let aseprite =
+            bevy_aseprite_reader::Aseprite::from_path(format!("assets/{}", TILESET_ASEPRITE))
+                .unwrap();
+        assert!(aseprite.frames().count() >= num_terrain_types());
+        let tags = aseprite.tags();
+        let water = tags.get_by_name(terrain_info(terrain::WATER).name).unwrap();
+        // animation frames come after the static ones
+        assert!(water.frames.start as usize >= num_terrain_types());
+        assert!(water.frames.len() > 1);
+    }
+}
@@ -20,6 +20,11 @@ impl Tile {
     }
     pub fn apply_restrictions(&mut self, restriction: &BitVec) -> (bool, bool) {
         let old_ones = self.allowed.count_ones();
+        if (self.allowed.clone() & restriction).not_any() {
+            // contradiction (e.g. between fixed tiles of two neighboring chunks): rather break a rule locally
+            // than end up with a tile without any state
+            return (false, false);
+        }
         self.allowed &= restriction;
 
         let ones = self.allowed.count_ones();
@@ -38,7 +43,12 @@ impl Tile {
     }
 }
 
-pub fn test(input_tiles: &HashMap<Cube, usize>) -> impl Iterator<Item = (Cube, usize)> {
+const NUM_STATES: usize = 4;
+
+// fill region with random tiles. fixed tiles inside the region are kept, fixed tiles directly outside of the
+// region only act as constraints (e.g. for continuing the neighboring chunks). Tile types that are not known to
+// the wavefunction are ignored.
+pub fn generate(region: &[Cube], fixed: &HashMap<Cube, usize>) -> Vec<(Cube, usize)> {
     let weights = vec![0.50, 0.05, 0.40, 0.05];
 
     let rules = [
@@ -63,35 +73,36 @@ pub fn test(input_tiles: &HashMap<Cube, usize>) -> impl Iterator<Item = (Cube, u
     {
         let mut dirty = Vec::new();
 
-        for y in 0..13 {
-            for x in 0..20 {
-                let v = Vec2::new(x as f32, y as f32);
-                let k = Cube::from_odd_r(v);
-                let mut tile = Tile::new(4);
+        for k in region.iter().copied() {
+            let mut tile = Tile::new(NUM_STATES);
 
-                if let Some(x) = input_tiles.get(&k) {
+            match fixed.get(&k) {
+                Some(x) if *x < NUM_STATES => {
                     tile.allowed.fill(false);
                     tile.allowed.set(*x, true);
 
                     dirty.push(k);
-                } else {
+                }
+                _ => {
                     uncollapsed.insert(k);
                 }
-                tiles.insert(k, tile);
+            }
+            tiles.insert(k, tile);
+        }
+        let border = region
+            .iter()
+            .flat_map(|k| CUBE_DIRECTIONS.iter().map(move |ndir| *k + *ndir))
+            .filter(|n| !tiles.contains_key(n))
+            .collect::<HashSet<_>>();
+        for n in border {
+            if let Some(x) = fixed.get(&n).filter(|x| **x < NUM_STATES) {
+                let mut tile = Tile::new(NUM_STATES);
+                tile.allowed.fill(false);
+                tile.allowed.set(*x, true);
+                tiles.insert(n, tile);
+                dirty.push(n);
             }
         }
-        // for y in 0..13 {
-        //     for x in 0..20 {
-        //         let v = Vec2::new(x as f32, y as f32);
-        //         let k = Cube::from_odd_r(v);
-        //         if y == 0 || y == 12 || (x == 0 || x == 19) {
-        //             let tile = tiles.get_mut(&k).unwrap();
-        //             tile.allowed.fill(false);
-        //             tile.allowed.set(0, true);
-        //             dirty.push(k);
-        //         }
-        //     }
-        // }
 
         while let Some(d) = dirty.pop() {
             let dirty_tile = tiles.get(&d).unwrap();
@@ -122,7 +133,7 @@ pub fn test(input_tiles: &HashMap<Cube, usize>) -> impl Iterator<Item = (Cube, u
 
         let tile = tiles.get_mut(&collapse).unwrap();
 
-        info!("allowed: {:?}", tile.allowed);
+        debug!("allowed: {:?}", tile.allowed);
         tile.collapse(&weights);
 
         let mut dirty = vec![collapse];
@@ -146,9 +157,10 @@ pub fn test(input_tiles: &HashMap<Cube, usize>) -> impl Iterator<Item = (Cube, u
             // println!("dirty: {:?}", dirty);
         }
     }
-    tiles
-        .into_iter()
-        .map(|(p, t)| (p, t.allowed.first_one().unwrap()))
+    region
+        .iter()
+        .map(|p| (*p, tiles[p].allowed.first_one().unwrap()))
+        .collect()
 }
 
 fn derive_neighbor_restriction(new_restriction: &BitVec, rules: &MultiMap<usize, usize>) -> BitVec {
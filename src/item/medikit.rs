@@ -1,4 +1,12 @@
-use crate::{ai::HealthPoints, path::Waypoint, sprites, Despawn};
+use crate::{
+    ai::HealthPoints,
+    hex::{
+        marker::{marker_positions, MapMarker, MarkerKind},
+        tilemap::Resources,
+    },
+    path::{TravelDistances, Waypoint, WaypointGraph},
+    sprites, Despawn,
+};
 use bevy::prelude::*;
 use bevy_aseprite::AsepriteBundle;
 use rand::prelude::SliceRandom;
@@ -8,6 +16,13 @@ use super::{Item, ItemContactProbe};
 #[derive(Component)]
 pub struct Medikit;
 
+// the medikit that can be reached quickest, closest by the way there (see TravelDistances), not as the crow
+// flies. None if there is none within TravelDistances::max_seconds.
+#[derive(Component, Default)]
+pub struct NearestMedikit {
+    pub pos: Option<Vec3>,
+}
+
 pub mod tune {
     pub const MEDIKIT_PICK_DIST: f32 = 8.0;
     pub const MEDIKIT_HEALTH: i32 = 10;
@@ -33,6 +48,37 @@ pub fn pick_medikit_system(
     }
 }
 
+pub fn nearest_medikit_system(
+    resources: Res<Resources>,
+    graph: Res<WaypointGraph>,
+    medikit_query: Query<&Transform, With<Medikit>>,
+    mut query: Query<(&TravelDistances, &mut NearestMedikit)>,
+) {
+    let medikits = medikit_query
+        .iter()
+        .filter_map(|transform| {
+            let cube = graph.nearest(transform.translation, resources.tile_size)?;
+            Some((transform.translation, cube))
+        })
+        .collect::<Vec<_>>();
+    for (distances, mut nearest) in query.iter_mut() {
+        let pos = distances.map.as_ref().and_then(|map| {
+            medikits
+                .iter()
+                .filter_map(|(pos, cube)| {
+                    let seconds =
+                        map.seconds(*cube, resources.tile_size, crate::tune::WALK_SPEED)?;
+                    Some((*pos, seconds))
+                })
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(pos, _)| pos)
+        });
+        if nearest.pos != pos {
+            nearest.pos = pos;
+        }
+    }
+}
+
 // pub fn pick_medikit_system(
 //     mut commands: Commands,
 //     query: Query<(Entity, &Transform), With<Medikit>>,
@@ -72,8 +118,9 @@ pub fn pick_medikit_system(
 pub fn spawn_medikits_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    query: Query<Entity, With<Medikit>>,
+    query: Query<&Transform, With<Medikit>>,
     waypoints_query: Query<&Transform, With<Waypoint>>,
+    marker_query: Query<(&MapMarker, &Transform)>,
 ) {
     let count = query.iter().count();
     if count >= tune::MEDIKIT_COUNT {
@@ -81,18 +128,33 @@ pub fn spawn_medikits_system(
     }
 
     let num_create = tune::MEDIKIT_COUNT - count;
-    let waypoint_pos = waypoints_query
-        .iter()
-        .map(|transform| transform.translation)
-        .collect::<Vec<_>>();
 
-    if waypoint_pos.len() < num_create {
+    // prefer (unoccupied) spawn points placed in the editor, otherwise fall back to random waypoints
+    let spawn_points = marker_positions(&marker_query, MarkerKind::MedikitSpawn);
+    let spawn_pos = if !spawn_points.is_empty() {
+        spawn_points
+            .into_iter()
+            .filter(|pos| {
+                !query.iter().any(|transform| {
+                    (transform.translation.truncate() - pos.truncate()).length()
+                        < tune::MEDIKIT_PICK_DIST
+                })
+            })
+            .collect::<Vec<_>>()
+    } else {
+        waypoints_query
+            .iter()
+            .map(|transform| transform.translation)
+            .collect::<Vec<_>>()
+    };
+
+    if spawn_pos.is_empty() {
         return;
     }
 
     let mut rng = rand::thread_rng();
 
-    for pos in waypoint_pos.choose_multiple(&mut rng, num_create) {
+    for pos in spawn_pos.choose_multiple(&mut rng, num_create) {
         commands
             .spawn_bundle(AsepriteBundle {
                 aseprite: asset_server.load(sprites::Medikit::PATH),
@@ -2,6 +2,8 @@ use std::collections::VecDeque;
 
 use bevy::prelude::*;
 
+use crate::{path, state::AppState};
+
 pub mod medikit;
 
 #[derive(Component)]
@@ -44,8 +46,14 @@ pub struct ItemPlugin;
 
 impl Plugin for ItemPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(medikit::pick_medikit_system)
-            .add_system(medikit::spawn_medikits_system)
-            .add_system(item_contact_system);
+        app.add_system_set(
+            SystemSet::on_update(AppState::Play)
+                .with_system(medikit::pick_medikit_system)
+                .with_system(medikit::spawn_medikits_system)
+                .with_system(
+                    medikit::nearest_medikit_system.after(path::update_travel_distances_system),
+                )
+                .with_system(item_contact_system),
+        );
     }
 }
@@ -1,10 +1,13 @@
 #![allow(clippy::uninlined_format_args)]
-#![feature(exclusive_range_pattern)]
 use bevy::{app::AppExit, prelude::*};
-// use bevy_ecs_tilemap::{MapQuery, Tile};
 use bevy_prototype_debug_lines::DebugLines;
-use hex::tilemap::{HexTileAppearance, HexTileCoord};
-use movement::crab_move::clip_movement;
+use debug::debug_draw_hex;
+use hex::tilemap::{HexTileAppearance, HexTileIndex, Resources};
+use hex::{
+    collision::{hex_center, sweep_circle},
+    destructible::TileHitEvent,
+};
+use movement::crab_move::is_solid;
 
 pub mod ai;
 pub mod brainy;
@@ -15,11 +18,14 @@ pub mod item;
 pub mod movement;
 pub mod path;
 pub mod pointer;
-pub mod tilemap;
+pub mod state;
 pub mod ui;
 
 pub mod tune {
     pub const WALK_SPEED: f32 = 15.0;
+    // collision circles against solid hexes
+    pub const WALKER_RADIUS: f32 = 3.0;
+    pub const PEW_RADIUS: f32 = 3.0;
     pub const PEW_SPEED: f32 = 50.0;
     pub const PEW_ZAP_DISTANCE: f32 = 8.0;
     pub const PEW_DETECT_FAR: f32 = 150.0;
@@ -27,6 +33,9 @@ pub mod tune {
 
     pub const AMMO_RELOAD_TIME: f32 = 0.5;
     pub const AMMO_RELOAD_AMOUNT: f32 = 3.0;
+
+    // medikits farther away (in seconds of walking) are not worth going for
+    pub const MEDIKIT_MAX_TRAVEL_TIME: f32 = 20.0;
 }
 pub mod sprites {
     use bevy_aseprite::aseprite;
@@ -46,6 +55,11 @@ pub struct TargetFlag;
 // TODO: move to proper package
 #[derive(Component)]
 pub struct Pew(pub bool, pub f32);
+
+// where the pew was before its last move. Hits are checked along the whole way, so fast pews (or low frame
+// rates) do not tunnel through walls and targets.
+#[derive(Component)]
+pub struct PewPrevPosition(pub Vec3);
 // #[derive(Component)]
 // pub struct TimeToLive(pub f32);
 
@@ -56,14 +70,18 @@ pub enum Despawn {
     TimeToLive(f32),
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn pew_move_system(
     mut commands: Commands,
     time: Res<Time>,
+    resources: Res<Resources>,
     mut debug_lines: ResMut<DebugLines>,
-    mut query: Query<(Entity, &Pew, &mut Transform)>,
-    tile_query: Query<(&HexTileCoord, &HexTileAppearance)>,
+    mut query: Query<(Entity, &Pew, &mut Transform, &mut PewPrevPosition)>,
+    index: Res<HexTileIndex>,
+    tile_query: Query<&HexTileAppearance>,
+    mut tile_hits: EventWriter<TileHitEvent>,
 ) {
-    for (entity, Pew(right, _), mut transform) in query.iter_mut() {
+    for (entity, Pew(right, power), mut transform, mut prev) in query.iter_mut() {
         let dir = if *right {
             Vec3::new(1.0, 0.0, 0.0)
         } else {
@@ -71,15 +89,31 @@ pub fn pew_move_system(
         } * time.delta_seconds()
             * tune::PEW_SPEED;
 
-        // // FIXME: it is not the smartest idea to use the clip_code to detect pew-wall collision, but it gets the job done quickly
-        let d = clip_movement(
-            &mut debug_lines,
-            &tile_query,
-            transform.translation,
-            dir,
-            0..1,
-        );
-        if d == Vec3::ZERO {
+        prev.0 = transform.translation;
+        if let Some((cube, hit)) = sweep_circle(
+            transform.translation.truncate(),
+            dir.truncate(),
+            tune::PEW_RADIUS,
+            resources.tile_size,
+            |cube| is_solid(&index, &tile_query, cube),
+        ) {
+            transform.translation += dir * hit.toi;
+            debug_draw_hex(
+                &mut debug_lines,
+                hex_center(cube, resources.tile_size).extend(0.0),
+                resources.tile_size,
+                Some(0.2),
+            );
+            debug_lines.line(
+                transform.translation,
+                transform.translation + hit.normal.extend(0.0) * 5.0,
+                0.2,
+            );
+            // walls take the remaining power of the pew as damage
+            tile_hits.send(TileHitEvent {
+                cube,
+                damage: *power,
+            });
             commands.entity(entity).insert(Despawn::ThisFrame);
         } else {
             transform.translation += dir;
@@ -2,7 +2,6 @@ use bevy::{diagnostic::DiagnosticsPlugin, prelude::*, render::texture::ImageSett
 // use bevy_aseprite::AsepritePlugin;
 use bevy_aseprite::{anim::AsepriteAnimation, AsepriteBundle, AsepritePlugin};
 
-use bevy_ecs_tilemap::TilemapPlugin;
 use bevy_egui::EguiPlugin;
 use big_brain::BigBrainPlugin;
 use game1::{
@@ -10,7 +9,10 @@ use game1::{
     brainy::spawn_brainy_ferris_system,
     die::die_system,
     exit_on_esc_system,
-    hex::tilemap::HexTilemapPlugin,
+    hex::{
+        marker::{marker_positions, MapMarker, MarkerKind},
+        tilemap::HexTilemapPlugin,
+    },
     item::{ItemContactProbe, ItemPlugin},
     movement::{
         crab_move::{CrabMoveDirection, CrabMoveWalker},
@@ -21,12 +23,12 @@ use game1::{
     path::{PathPlugin, Waypoint},
     pointer::{ClickEvent, MousePointerFlag, PointerPlugin},
     sprites,
-    tilemap::PlayfieldPlugin,
+    state::{AppState, AppStatePlugin},
     tune,
     ui::IngameUiPlugin,
-    Despawn, InputTarget, Pew, TargetFlag,
+    Despawn, InputTarget, Pew, PewPrevPosition, TargetFlag,
 };
-use rand::{thread_rng, Rng};
+use rand::{seq::SliceRandom, thread_rng, Rng};
 
 fn main() {
     let mut app = App::new();
@@ -39,7 +41,6 @@ fn main() {
     //
     app.add_plugins(DefaultPlugins)
         .add_plugin(DiagnosticsPlugin)
-        .add_plugin(TilemapPlugin)
         .add_plugin(AsepritePlugin)
         .add_plugin(BigBrainPlugin)
         .add_plugin(bevy_prototype_debug_lines::DebugLinesPlugin::with_depth_test(true))
@@ -48,15 +49,15 @@ fn main() {
     //
     // internal plugins
     //
-    app.add_plugin(PointerPlugin)
+    app.add_plugin(AppStatePlugin)
+        .add_plugin(PointerPlugin)
         .add_plugin(MovementPlugin)
         .add_plugin(AiPlugin)
         .add_plugin(PathPlugin)
         .add_plugin(AiDiagnosticsPlugin)
         .add_plugin(IngameUiPlugin)
-        .add_plugin(PlayfieldPlugin)
         .add_plugin(ItemPlugin)
-        .add_plugin(HexTilemapPlugin);
+        .add_plugin(HexTilemapPlugin::default());
 
     //
     // startup systems
@@ -67,15 +68,18 @@ fn main() {
     // systems (mostly: TODO move to plugins)
     //
     app.add_system(setup_camera)
-        .add_system(walk_to_target)
-        .add_system(apply_input)
         .add_system(exit_on_esc_system)
         // .add_system(spawn_waypoint_on_click)
-        .add_system(game1::pew_move_system)
         .add_system_to_stage(CoreStage::PostUpdate, game1::despawn_reaper_system)
-        .add_system(die_system)
-        .add_system(spawn_brainy_ferris_system)
-        .add_system(spawn_player_system);
+        .add_system_set(
+            SystemSet::on_update(AppState::Play)
+                .with_system(walk_to_target)
+                .with_system(apply_input)
+                .with_system(game1::pew_move_system)
+                .with_system(die_system)
+                .with_system(spawn_brainy_ferris_system)
+                .with_system(spawn_player_system),
+        );
     //
     // type registrations
     //
@@ -266,6 +270,7 @@ fn apply_input(
                     ..Default::default()
                 })
                 .insert(Pew(walk_velocity.direction.is_right(), 15.0))
+                .insert(PewPrevPosition(transform.translation + offset))
                 .insert(Despawn::TimeToLive(10.0));
         }
         // else if keyboard_input.just_pressed(KeyCode::K) {
@@ -310,8 +315,14 @@ pub fn spawn_player_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     query: Query<(), With<InputTarget>>,
+    marker_query: Query<(&MapMarker, &Transform)>,
 ) {
     if query.is_empty() {
-        spawn_player(&mut commands, &asset_server, Vec3::new(40., 112., 5.));
+        let spawn_points = marker_positions(&marker_query, MarkerKind::PlayerSpawn);
+        let pos = spawn_points
+            .choose(&mut thread_rng())
+            .map(|pos| *pos + Vec3::Z * 5.0)
+            .unwrap_or_else(|| Vec3::new(40., 112., 5.));
+        spawn_player(&mut commands, &asset_server, pos);
     }
 }
@@ -5,6 +5,8 @@ use bevy::prelude::*;
 //   currently it is easy as threats can only come horizontally, so vertical movement always works)
 // * go to point: try to reach the point 'somehow'. How this is done (path finding, movement mechanics)
 //   is up to lower levels.
+// * follow target: like go to point, but the point is the TargetFlag entity. Everyone chasing it shares the
+//   same TargetFlowField.
 //
 // also it is a good idea to have some sensible priority ordering of the movements, e.g. evasion should
 // override go to point.
@@ -20,3 +22,10 @@ pub struct MovementEvade {
 #[derive(Component)]
 #[component(storage = "SparseSet")]
 pub struct MovementGoToPoint(pub Vec3);
+
+// done once within until of the target
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct MovementFollowTarget {
+    pub until: f32,
+}
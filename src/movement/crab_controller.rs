@@ -1,12 +1,19 @@
+use std::collections::HashMap;
+
 use crate::{
+    hex::tilemap::Resources,
     movement::crab_move::{CrabMoveDirection, CrabMoveWalker},
-    path::{PathQuery, Waypoint, WaypointPath},
+    path::{
+        PathFailed, PathPriority, PathQuery, PathStatus, TargetFlowField, WaypointGraph,
+        WaypointPath,
+    },
+    TargetFlag,
 };
 
 use bevy::prelude::*;
 use rand::Rng;
 
-use super::control::{MovementEvade, MovementGoToPoint};
+use super::control::{MovementEvade, MovementFollowTarget, MovementGoToPoint};
 
 // implementation of the abstract movement controls for CrabMoveWalker
 
@@ -16,7 +23,6 @@ mod tune {
 
 #[derive(Component, Default)]
 #[component(storage = "SparseSet")]
-
 pub struct CrabFollowPath {
     pub next_step: usize,
 }
@@ -55,8 +61,18 @@ pub fn crab_evade_system(
 #[allow(clippy::type_complexity)]
 pub fn crab_update_path_system(
     mut commands: Commands,
+    resources: Res<Resources>,
+    graph: Res<WaypointGraph>,
+    mut failed_events: EventReader<PathFailed>,
+    go_to_point_query: Query<(), With<MovementGoToPoint>>,
     query: Query<
-        (Entity, &Transform, &MovementGoToPoint),
+        (
+            Entity,
+            &Transform,
+            &MovementGoToPoint,
+            ChangeTrackers<MovementGoToPoint>,
+            Option<&PathStatus>,
+        ),
         (
             Without<CrabFollowPath>,
             Without<PathQuery>,
@@ -64,15 +80,35 @@ pub fn crab_update_path_system(
         ),
     >,
 ) {
+    // nothing to follow. The MovementGoToPoint stays, whoever set it can check the PathStatus.
+    for PathFailed { entity } in failed_events.iter() {
+        if go_to_point_query.get(*entity).is_ok() {
+            commands.entity(*entity).remove::<CrabFollowPath>();
+        }
+    }
+
     for (
         entity,
         Transform {
             translation: start, ..
         },
         MovementGoToPoint(end),
+        go_to_point_tracker,
+        status,
     ) in query.iter()
     {
-        if (*start - *end).length() < 10.0 {
+        // asking again would not help, unless there is a new point to go to
+        if status == Some(&PathStatus::Failed) && !go_to_point_tracker.is_changed() {
+            continue;
+        }
+        // points inside walls are reached as far as possible (see WaypointGraph::nearest)
+        let end_cube = resources.world_to_cube(*end);
+        let start_cube = resources.world_to_cube(*start);
+        let snapped_end = match graph.nearest(*end, resources.tile_size) {
+            Some(cube) if cube != end_cube => Some(cube),
+            _ => None,
+        };
+        if (*start - *end).length() < 10.0 || snapped_end == Some(start_cube) {
             commands
                 .entity(entity)
                 .remove::<MovementGoToPoint>()
@@ -85,6 +121,7 @@ pub fn crab_update_path_system(
             .insert(PathQuery {
                 start: *start,
                 end: *end,
+                priority: PathPriority::Normal,
             })
             .insert(CrabFollowPath::default());
     }
@@ -102,7 +139,6 @@ pub fn crab_follow_path_system(
         ),
         Without<MovementEvade>,
     >,
-    waypoint_query: Query<&Transform, With<Waypoint>>,
 ) {
     for (
         entity,
@@ -117,25 +153,100 @@ pub fn crab_follow_path_system(
             continue;
         }
         let min_dist = 6.0;
-        if let Ok(Transform {
-            translation: waypoint_translation,
-            ..
-        }) = waypoint_query.get(waypoints[follow_path.next_step])
-        {
-            let d = *waypoint_translation - *translation;
-            let tv = d.normalize();
-            walker.direction = CrabMoveDirection::find_nearest(tv);
-            debug!(
-                "follow path progress: {} {} {:?}",
-                follow_path.next_step,
-                d.length(),
-                waypoint_translation
-            );
-
-            if d.length() < min_dist {
-                // info!("follow path next step: {}", follow_path.next_step);
-                follow_path.next_step += 1;
-            }
+        let waypoint_translation = waypoints[follow_path.next_step];
+        let d = waypoint_translation - *translation;
+        walker.steer_towards(d.normalize());
+        debug!(
+            "follow path progress: {} {} {:?}",
+            follow_path.next_step,
+            d.length(),
+            waypoint_translation
+        );
+
+        if d.length() < min_dist {
+            // info!("follow path next step: {}", follow_path.next_step);
+            follow_path.next_step += 1;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn crab_follow_target_system(
+    mut commands: Commands,
+    resources: Res<Resources>,
+    graph: Res<WaypointGraph>,
+    flow_field: Res<TargetFlowField>,
+    target_query: Query<&Transform, With<TargetFlag>>,
+    mut failed_events: EventWriter<PathFailed>,
+    status_query: Query<&PathStatus>,
+    mut query: Query<
+        (
+            Entity,
+            &mut CrabMoveWalker,
+            &MovementFollowTarget,
+            &Transform,
+            Option<&PathStatus>,
+        ),
+        Without<MovementEvade>,
+    >,
+    // field generation in which the target was found unreachable
+    mut failed: Local<HashMap<Entity, u32>>,
+) {
+    // a failure holds until the field changes, then the target may be reachable again
+    failed.retain(|entity, generation| {
+        if *generation == flow_field.generation {
+            return true;
+        }
+        if status_query.get(*entity) == Ok(&PathStatus::Failed) {
+            commands.entity(*entity).remove::<PathStatus>();
+        }
+        false
+    });
+
+    let target_pos = match target_query.iter().next() {
+        Some(transform) => transform.translation,
+        None => return,
+    };
+
+    for (
+        entity,
+        mut walker,
+        MovementFollowTarget { until },
+        Transform { translation, .. },
+        status,
+    ) in query.iter_mut()
+    {
+        if (target_pos - *translation).length() < *until {
+            commands.entity(entity).remove::<MovementFollowTarget>();
+            continue;
+        }
+        if failed.contains_key(&entity) && status == Some(&PathStatus::Failed) {
+            continue;
+        }
+        // the hex next to a wall counts when the follower was pushed into it
+        let cube = graph.nearest(*translation, resources.tile_size);
+        let next = match (&flow_field.field, cube) {
+            (Some(field), Some(cube)) if cube != field.goal => match field.next(cube) {
+                Some(next) => Some(next),
+                None => {
+                    // the target cannot be reached from here
+                    commands.entity(entity).insert(PathStatus::Failed);
+                    failed_events.send(PathFailed { entity });
+                    failed.insert(entity, flow_field.generation);
+                    continue;
+                }
+            },
+            _ => None,
+        };
+        failed.remove(&entity);
+        if status != Some(&PathStatus::Found) {
+            commands.entity(entity).insert(PathStatus::Found);
+        }
+        // head for the center of the next hex. Straight for the target on the last hex (and without a field)
+        let next_pos = next.map_or(target_pos, |next| resources.cube_to_world(next));
+        let d = next_pos - *translation;
+        if d.length() > f32::EPSILON {
+            walker.direction = CrabMoveDirection::find_nearest(d.normalize());
         }
     }
 }
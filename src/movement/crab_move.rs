@@ -1,20 +1,29 @@
-use std::ops::Range;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    debug::debug_draw_box,
-    hex::tilemap::{HexTileAppearance, HexTileCoord},
+    ai::HealthPoints,
+    debug::debug_draw_hex,
+    hex::{
+        collision::{circle_overlaps_solid, hex_center, slide_circle},
+        terrain::{terrain_info, TerrainInfo, GROUND},
+        tilemap::{HexTileAppearance, HexTileIndex, Resources},
+        Cube,
+    },
     pointer::MouseGrabState,
-    sprites, tune,
+    sprites, tune, InputTarget,
 };
-use bevy::{prelude::*, sprite::collide_aabb::collide};
+use bevy::prelude::*;
 use bevy_aseprite::anim::AsepriteAnimation;
-// use bevy_ecs_tilemap::{MapQuery, Tile, TilePos};
 use bevy_prototype_debug_lines::DebugLines;
 
-use super::zap::BeingZapped;
+use super::{
+    separation::{steer, Neighbours},
+    zap::BeingZapped,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CrabMoveDirection {
+    #[default]
     None,
     West,
     NorthWest,
@@ -23,16 +32,30 @@ pub enum CrabMoveDirection {
     SouthEast,
     SouthWest,
 }
-impl Default for CrabMoveDirection {
-    fn default() -> Self {
-        CrabMoveDirection::None
-    }
-}
 
 #[derive(Component, Default)]
 // #[reflect(Component)]
 pub struct CrabMoveWalker {
     pub direction: CrabMoveDirection,
+    // actual velocity, lags behind direction on slippery terrain
+    pub velocity: Vec3,
+    // exact direction set by steer_towards, walked as long as direction is not changed otherwise
+    heading: Option<(CrabMoveDirection, Vec3)>,
+}
+
+impl CrabMoveWalker {
+    // walk along dir instead of the closest of the six hex directions (which is still used for the animation)
+    pub fn steer_towards(&mut self, dir: Vec3) {
+        self.direction = CrabMoveDirection::find_nearest(dir);
+        self.heading = Some((self.direction, dir));
+    }
+
+    pub fn wanted_direction(&self) -> Vec3 {
+        match self.heading {
+            Some((direction, dir)) if direction == self.direction => dir,
+            _ => self.direction.to_vec3(),
+        }
+    }
 }
 
 const HEX_DIAG_X: f32 = 0.5;
@@ -92,16 +115,20 @@ impl CrabMoveDirection {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn apply_velocity_system(
     time: Res<Time>,
+    resources: Res<Resources>,
     mut query: Query<(
         Entity,
         &mut Transform,
         &mut AsepriteAnimation,
-        &CrabMoveWalker,
+        &mut CrabMoveWalker,
     )>,
     zapped_query: Query<Entity, With<BeingZapped>>,
-    tile_query2: Query<(&HexTileCoord, &HexTileAppearance)>,
+    player_query: Query<(), With<InputTarget>>,
+    index: Res<HexTileIndex>,
+    tile_query: Query<&HexTileAppearance>,
     grab_state: ResMut<MouseGrabState>,
     mut debug_lines: ResMut<DebugLines>,
 ) {
@@ -111,7 +138,19 @@ pub fn apply_velocity_system(
 
     // map_query.
 
-    for (entity, mut transform, mut animation, walk_velocity) in query.iter_mut() {
+    // positions before anyone moves this frame, so the order does not matter. The player is steered by the input
+    // alone, the crabs walk around it.
+    let (players, walkers): (Vec<_>, Vec<_>) = query
+        .iter()
+        .map(|(entity, transform, _, _)| (entity, transform.translation.truncate()))
+        .partition(|(entity, _)| player_query.get(*entity).is_ok());
+    let neighbours = Neighbours::new(
+        walkers.into_iter(),
+        players.into_iter(),
+        resources.tile_size,
+    );
+
+    for (entity, mut transform, mut animation, mut walker) in query.iter_mut() {
         if zapped_query.get(entity).is_ok() {
             if !animation.is_tag(sprites::Ferris::tags::ZAP) {
                 *animation = AsepriteAnimation::from(sprites::Ferris::tags::ZAP)
@@ -119,36 +158,46 @@ pub fn apply_velocity_system(
             continue;
         }
 
-        let velocity = walk_velocity.direction.to_vec3();
+        let velocity = walker.wanted_direction();
         let speed = velocity.length();
 
         debug!(
             "walk: {:?} {:?} {:?}",
             entity, transform.translation, velocity
         );
-        if speed > 0.1 {
-            let dir = velocity.normalize();
-            let delta = tune::WALK_SPEED * dir * time.delta_seconds();
-            let x_delta = Vec3::new(delta.x, 0.0, 0.0);
-            let y_delta = Vec3::new(0.0, delta.y, 0.0);
+        let terrain = terrain_at(&resources, &index, &tile_query, transform.translation);
+        let direction = if speed > 0.1 {
+            velocity.normalize()
+        } else {
+            Vec3::ZERO
+        };
+        let separation = neighbours.separation(entity, transform.translation.truncate());
+        let target_velocity = steer(
+            direction.truncate(),
+            separation,
+            tune::WALK_SPEED * terrain.speed,
+        )
+        .extend(0.0);
+        let dt = time.delta_seconds();
+        let blend = 1.0 - terrain.slipperiness.powf(dt);
+        walker.velocity = walker.velocity.lerp(target_velocity, blend);
 
-            let x_delta = clip_movement(
-                &mut debug_lines,
-                &tile_query2,
-                transform.translation,
-                x_delta,
-                0..1,
-            );
-            let y_delta = clip_movement(
+        if walker.velocity.length() > 0.01 && dt > 0.0 {
+            let delta = clip_movement(
                 &mut debug_lines,
-                &tile_query2,
+                |cube| is_solid(&index, &tile_query, cube),
+                resources.tile_size,
                 transform.translation,
-                y_delta,
-                0..1,
+                walker.velocity * dt,
+                tune::WALKER_RADIUS,
             );
+            transform.translation += delta;
+            // walls also stop sliding
+            walker.velocity = delta / dt;
+        }
 
-            transform.translation += x_delta;
-            transform.translation += y_delta;
+        if speed > 0.1 {
+            let dir = walker.direction.to_vec3();
             // animation.
             if dir.x > 0.0 && !animation.is_tag(sprites::Ferris::tags::WALK_RIGHT) {
                 *animation = AsepriteAnimation::from(sprites::Ferris::tags::WALK_RIGHT);
@@ -165,46 +214,88 @@ pub fn apply_velocity_system(
     }
 }
 
-// FIXME: movement clipping is based on aabb of hex tile -> very crappy
+// terrain of the hex at pos. Where there is no tile (yet) it behaves like plain ground.
+pub fn terrain_at(
+    resources: &Resources,
+    index: &HexTileIndex,
+    tile_query: &Query<&HexTileAppearance>,
+    pos: Vec3,
+) -> &'static TerrainInfo {
+    let tile_type = index
+        .tiles
+        .get(&resources.world_to_cube(pos))
+        .and_then(|entity| tile_query.get(*entity).ok())
+        .map_or(GROUND, |appearance| appearance.tile_type);
+    terrain_info(tile_type)
+}
+
+// fractional terrain damage is collected until it makes up a full health point
+pub fn terrain_damage_system(
+    time: Res<Time>,
+    resources: Res<Resources>,
+    index: Res<HexTileIndex>,
+    tile_query: Query<&HexTileAppearance>,
+    mut query: Query<(Entity, &Transform, &mut HealthPoints), With<CrabMoveWalker>>,
+    mut pending: Local<HashMap<Entity, f32>>,
+) {
+    let mut seen = HashSet::new();
+    for (entity, transform, mut health_points) in query.iter_mut() {
+        let terrain = terrain_at(&resources, &index, &tile_query, transform.translation);
+        if terrain.damage_per_second <= 0.0 {
+            continue;
+        }
+        seen.insert(entity);
+        let damage = pending.entry(entity).or_default();
+        *damage += terrain.damage_per_second * time.delta_seconds();
+        let whole = damage.floor();
+        if whole >= 1.0 {
+            health_points.health -= whole as i32;
+            *damage -= whole;
+        }
+    }
+    pending.retain(|entity, _| seen.contains(entity));
+}
+
+// collision only looks at the hexes around the mover, via the tile index (so the cost does not depend on the
+// map size). Hexes without a tile are not solid.
+pub fn is_solid(index: &HexTileIndex, tile_query: &Query<&HexTileAppearance>, cube: Cube) -> bool {
+    index
+        .tiles
+        .get(&cube)
+        .and_then(|entity| tile_query.get(*entity).ok())
+        .is_some_and(|appearance| !terrain_info(appearance.tile_type).walkable)
+}
+
+// move a circle of the given radius, sliding along solid hexes
 pub fn clip_movement(
     debug_lines: &mut DebugLines,
-    tile_query: &Query<(&HexTileCoord, &HexTileAppearance)>,
+    is_solid: impl Fn(Cube) -> bool,
+    tile_size: Vec2,
     translation: Vec3,
     delta: Vec3,
-    solid_range: Range<usize>,
+    radius: f32,
 ) -> Vec3 {
-    // FIXME: the hard coded 256 256 offset is crap!
-    let box_size = Vec2::new(18.0, 20.0);
-
-    let aabbs: Vec<_> = tile_query
-        .iter()
-        .filter_map(|(coord, app)| {
-            if solid_range.contains(&app.tile_type) {
-                Some((
-                    (coord.cube.to_odd_r_screen() * box_size).extend(0.0),
-                    box_size * Vec2::new(1.0, 0.75),
-                ))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    // info!("aabbs: {:?}", aabbs);
-
-    // use very small player box to make clipping bearable
-    let player_size = Vec2::new(6.0, 6.0);
-    // debug_draw_box(debug_lines, translation + delta, player_size, Some(0.2));
-
-    // check collision with (non walkable) neighbor tiles
-    for aabb in aabbs.iter() {
-        if collide(translation + delta, player_size, aabb.0, aabb.1).is_some() {
-            // info!("collision");
-            debug_draw_box(debug_lines, aabb.0, aabb.1, Some(0.2));
-
-            return Vec3::ZERO;
+    let moved = slide_circle(
+        translation.truncate(),
+        delta.truncate(),
+        radius,
+        tile_size,
+        &is_solid,
+    );
+    if moved != delta.truncate() {
+        if let Some(cube) = circle_overlaps_solid(
+            (translation + delta).truncate(),
+            radius,
+            tile_size,
+            &is_solid,
+        ) {
+            debug_draw_hex(
+                debug_lines,
+                hex_center(cube, tile_size).extend(0.0),
+                tile_size,
+                Some(0.2),
+            );
         }
     }
-
-    delta
+    moved.extend(0.0)
 }
@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 
+use crate::state::AppState;
+
 pub mod control;
 pub mod crab_controller;
 pub mod crab_move;
+pub mod separation;
 pub mod walk;
 pub mod zap;
 
@@ -10,12 +13,17 @@ pub struct MovementPlugin;
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(crab_move::apply_velocity_system)
-            .add_system(walk::apply_velocity_system)
-            .add_system(zap::check_pew_intersection_system)
-            .add_system(crab_controller::crab_evade_system)
-            .add_system(crab_controller::crab_follow_path_system)
-            .add_system(crab_controller::crab_update_path_system)
-            .add_system(zap::apply_zap_damage);
+        app.add_system_set(
+            SystemSet::on_update(AppState::Play)
+                .with_system(crab_move::apply_velocity_system)
+                .with_system(crab_move::terrain_damage_system)
+                .with_system(walk::apply_velocity_system)
+                .with_system(zap::check_pew_intersection_system)
+                .with_system(crab_controller::crab_evade_system)
+                .with_system(crab_controller::crab_follow_path_system)
+                .with_system(crab_controller::crab_follow_target_system)
+                .with_system(crab_controller::crab_update_path_system)
+                .with_system(zap::apply_zap_damage),
+        );
     }
 }
@@ -0,0 +1,229 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::hex::Cube;
+
+// local avoidance between walkers: steering separation. Every walker is pushed away from the walkers that are
+// closer than SEPARATION_DISTANCE, on top of where it wants to go. Groups on the same path spread out instead
+// of walking through each other and stacking up at the end. Obstacles (the player) make the walkers step aside,
+// but are not pushed themselves.
+
+mod tune {
+    // walkers closer than this push each other apart (center to center, walkers are 2 * WALKER_RADIUS wide)
+    pub const SEPARATION_DISTANCE: f32 = 9.0;
+    // push at zero distance, relative to the walk speed. Above 1.0 so that walkers that want to go to the
+    // same point still make room.
+    pub const SEPARATION_STRENGTH: f32 = 1.5;
+    // walkers on the exact same spot have no direction to push each other to
+    pub const EPSILON: f32 = 1e-4;
+}
+
+// walkers bucketed by hex. Hexes are bigger than SEPARATION_DISTANCE, so only the neighbouring hexes have to
+// be looked at.
+pub struct Neighbours {
+    tile_size: Vec2,
+    buckets: HashMap<Cube, Vec<(Entity, Vec2)>>,
+    obstacles: HashSet<Entity>,
+}
+
+impl Neighbours {
+    pub fn new(
+        walkers: impl Iterator<Item = (Entity, Vec2)>,
+        obstacles: impl Iterator<Item = (Entity, Vec2)>,
+        tile_size: Vec2,
+    ) -> Self {
+        let mut neighbours = Neighbours {
+            tile_size,
+            buckets: HashMap::new(),
+            obstacles: HashSet::new(),
+        };
+        for (entity, pos) in walkers {
+            neighbours.add(entity, pos);
+        }
+        for (entity, pos) in obstacles {
+            neighbours.obstacles.insert(entity);
+            neighbours.add(entity, pos);
+        }
+        neighbours
+    }
+
+    fn add(&mut self, entity: Entity, pos: Vec2) {
+        self.buckets
+            .entry(to_cube(pos, self.tile_size))
+            .or_default()
+            .push((entity, pos));
+    }
+
+    // push away from the other walkers and obstacles, in walk speeds. Obstacles are never pushed.
+    pub fn separation(&self, entity: Entity, pos: Vec2) -> Vec2 {
+        if self.obstacles.contains(&entity) {
+            return Vec2::ZERO;
+        }
+        let center = to_cube(pos, self.tile_size);
+        let mut push = Vec2::ZERO;
+        for cube in center.ring(0).into_iter().chain(center.ring(1)) {
+            for (other, other_pos) in self.buckets.get(&cube).into_iter().flatten() {
+                if *other == entity {
+                    continue;
+                }
+                let d = pos - *other_pos;
+                let distance = d.length();
+                if distance >= tune::SEPARATION_DISTANCE {
+                    continue;
+                }
+                let away = if distance > tune::EPSILON {
+                    d / distance
+                } else if entity < *other {
+                    // always the same way, so both do not end up on the same spot again
+                    Vec2::X
+                } else {
+                    -Vec2::X
+                };
+                push += away * (1.0 - distance / tune::SEPARATION_DISTANCE);
+            }
+        }
+        push * tune::SEPARATION_STRENGTH
+    }
+}
+
+// velocity of a walker that wants to go in direction (unit length or zero), pushed by separation. Never faster
+// than max_speed.
+pub fn steer(direction: Vec2, separation: Vec2, max_speed: f32) -> Vec2 {
+    ((direction + separation) * max_speed).clamp_length_max(max_speed)
+}
+
+fn to_cube(pos: Vec2, tile_size: Vec2) -> Cube {
+    Cube::from_odd_r_screen((pos + tile_size * 0.5) / tile_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILE_SIZE: Vec2 = Vec2::new(18.0, 20.0);
+    const SPEED: f32 = 15.0;
+    const RADIUS: f32 = 3.0;
+    const DT: f32 = 1.0 / 60.0;
+
+    // walkers that all want to go to goal, moved with fixed time steps. The first num_obstacles of them are
+    // obstacles (players). Returns their positions.
+    fn simulate(
+        start: &[Vec2],
+        goal: Vec2,
+        steps: usize,
+        separate: bool,
+        num_obstacles: usize,
+    ) -> Vec<Vec2> {
+        let mut positions = start.to_vec();
+        for _ in 0..steps {
+            let entities = positions
+                .iter()
+                .enumerate()
+                .map(|(i, pos)| (Entity::from_raw(i as u32), *pos));
+            let neighbours = Neighbours::new(
+                entities.clone().skip(num_obstacles),
+                entities.take(num_obstacles),
+                TILE_SIZE,
+            );
+            positions = positions
+                .iter()
+                .enumerate()
+                .map(|(i, pos)| {
+                    let to_goal = goal - *pos;
+                    // stop at the goal, like the path following does
+                    let direction = if to_goal.length() > 6.0 {
+                        to_goal.normalize()
+                    } else {
+                        Vec2::ZERO
+                    };
+                    let separation = if separate {
+                        neighbours.separation(Entity::from_raw(i as u32), *pos)
+                    } else {
+                        Vec2::ZERO
+                    };
+                    *pos + steer(direction, separation, SPEED) * DT
+                })
+                .collect();
+        }
+        positions
+    }
+
+    fn min_distance(positions: &[Vec2]) -> f32 {
+        let mut min = f32::MAX;
+        for (i, a) in positions.iter().enumerate() {
+            for b in positions[i + 1..].iter() {
+                min = min.min(a.distance(*b));
+            }
+        }
+        min
+    }
+
+    // a tight group of 7, two of them on the same spot
+    fn group() -> Vec<Vec2> {
+        vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(-1.0, 2.0),
+            Vec2::new(1.0, -2.0),
+            Vec2::new(-2.0, -1.0),
+            Vec2::new(3.0, 3.0),
+        ]
+    }
+
+    #[test]
+    fn group_spreads_out_on_the_way() {
+        let goal = Vec2::new(200.0, 40.0);
+        let start = group();
+        let center = |positions: &[Vec2]| positions.iter().sum::<Vec2>() / positions.len() as f32;
+
+        let positions = simulate(&start, goal, 120, true, 0);
+        assert!(min_distance(&positions) > 2.0 * RADIUS, "{:?}", positions);
+        // still on the way: separation does not stop them
+        let progress =
+            (center(&positions) - center(&start)).dot((goal - center(&start)).normalize());
+        assert!(progress > 0.5 * SPEED * 120.0 * DT, "{}", progress);
+
+        // same inputs, same result
+        assert_eq!(positions, simulate(&start, goal, 120, true, 0));
+
+        // without separation they stay on top of each other
+        let positions = simulate(&start, goal, 120, false, 0);
+        assert!(min_distance(&positions) < RADIUS, "{:?}", positions);
+    }
+
+    #[test]
+    fn group_does_not_stack_at_goal() {
+        let goal = Vec2::new(60.0, 0.0);
+        let positions = simulate(&group(), goal, 600, true, 0);
+        assert!(min_distance(&positions) > 2.0 * RADIUS, "{:?}", positions);
+        // but they gather around it
+        for pos in positions.iter() {
+            assert!(
+                pos.distance(goal) < 4.0 * tune::SEPARATION_DISTANCE,
+                "{:?}",
+                positions
+            );
+        }
+    }
+
+    #[test]
+    fn obstacles_are_not_pushed() {
+        // the player starts in the middle of the group (on the same spot as a walker) and walks to the goal
+        let goal = Vec2::new(60.0, 0.0);
+        let positions = simulate(&group(), goal, 600, true, 1);
+        let player = positions[0];
+        // straight there, as if nobody else was around
+        assert_eq!(player, simulate(&[Vec2::ZERO], goal, 600, true, 1)[0]);
+        assert!(player.distance(goal) <= 6.0, "{:?}", positions);
+        // the walkers made room
+        for pos in positions[1..].iter() {
+            assert!(pos.distance(player) > RADIUS, "{:?}", positions);
+        }
+
+        // as a walker the player gets pushed aside as well
+        let positions = simulate(&group(), goal, 600, true, 0);
+        assert_ne!(positions[0].y, 0.0, "{:?}", positions);
+    }
+}
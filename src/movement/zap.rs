@@ -1,56 +1,103 @@
 use bevy::prelude::*;
+use bevy_prototype_debug_lines::DebugLines;
 
-use crate::{ai::HealthPoints, tune, Despawn, Pew};
+use crate::{
+    ai::HealthPoints,
+    hex::collision::{sweep_circle_circle, SweepHit},
+    tune, Despawn, Pew, PewPrevPosition,
+};
 
 #[derive(Component)]
 pub struct Zappable;
 
+// where the pew hit (last time) and the hit normal, pointing from the zapped entity towards the pew
 #[derive(Component)]
 #[component(storage = "SparseSet")]
-pub struct BeingZapped;
+pub struct BeingZapped {
+    pub pos: Vec3,
+    pub normal: Vec2,
+}
 
 // FIXME: handling of zap damage is complete crap:
 //  - decoupling between 'begin zapped' detection and applying damage is weird
 //  - pews only lose power the first time they hit a 'non-zapped' entity
 //  - but amount of damage is relative to the duration of the 'being zapped' state
 
+// did the pew come closer than PEW_ZAP_DISTANCE to pos on its last move, and when
+fn pew_hits(from: Vec3, to: Vec3, pos: Vec3) -> Option<SweepHit> {
+    sweep_circle_circle(
+        from.truncate(),
+        (to - from).truncate(),
+        0.0,
+        pos.truncate(),
+        tune::PEW_ZAP_DISTANCE,
+    )
+}
+
+fn impact_pos(from: Vec3, to: Vec3, hit: &SweepHit) -> Vec3 {
+    from + (to - from) * hit.toi
+}
+
 #[allow(clippy::type_complexity)]
 pub fn check_pew_intersection_system(
     _time: Res<Time>,
     mut commands: Commands,
-    query_non_zapped: Query<(Entity, &mut Transform), (With<Zappable>, Without<BeingZapped>)>,
-    query_zapped: Query<(Entity, &mut Transform), (With<Zappable>, With<BeingZapped>)>,
-    mut query_pew: Query<(&Transform, &mut Pew, Entity), Without<Zappable>>,
+    mut debug_lines: ResMut<DebugLines>,
+    query_non_zapped: Query<(Entity, &Transform), (With<Zappable>, Without<BeingZapped>)>,
+    mut query_zapped: Query<(Entity, &Transform, &mut BeingZapped), With<Zappable>>,
+    mut query_pew: Query<(&mut Transform, &PewPrevPosition, &mut Pew, Entity), Without<Zappable>>,
 ) {
     let mut pew_pos = query_pew
         .iter_mut()
-        .map(|(transform, pew, entity)| (transform.translation, pew, entity))
+        .map(|(transform, prev, pew, entity)| {
+            (prev.0, transform.translation, transform, pew, entity)
+        })
         .collect::<Vec<_>>();
 
-    const ZAP_DIST: f32 = tune::PEW_ZAP_DISTANCE;
-
     // determine which non-zapped entities get hit this frame
     for (entity, Transform { translation, .. }) in query_non_zapped.iter() {
-        if let Some((_, ref mut pew, pew_entity)) = pew_pos
-            .iter_mut()
-            .find(|(pos, _, _)| (*pos - *translation).length() < ZAP_DIST)
+        if let Some((pos, hit, pew_transform, pew, pew_entity)) =
+            pew_pos
+                .iter_mut()
+                .find_map(|(from, to, pew_transform, pew, pew_entity)| {
+                    let hit = pew_hits(*from, *to, *translation)?;
+                    Some((
+                        impact_pos(*from, *to, &hit),
+                        hit,
+                        pew_transform,
+                        pew,
+                        *pew_entity,
+                    ))
+                })
         {
-            commands.entity(entity).insert(BeingZapped);
+            debug_lines.line(pos, pos + hit.normal.extend(0.0) * 5.0, 0.2);
+            commands.entity(entity).insert(BeingZapped {
+                pos,
+                normal: hit.normal,
+            });
             // pew.1 -= time.delta_seconds() * 120.0;
             pew.1 -= 5.0; // time invariant!
             if pew.1 <= 0.0 {
-                commands.entity(*pew_entity).insert(Despawn::ThisFrame);
+                // the spent pew ends where it hit, not where its move would have ended
+                pew_transform.translation = pos;
+                commands.entity(pew_entity).insert(Despawn::ThisFrame);
             }
         }
     }
 
     // determine which being-zapped entities become non-zapped
-    for (entity, Transform { translation, .. }) in query_zapped.iter() {
-        if !pew_pos
+    for (entity, Transform { translation, .. }, mut zapped) in query_zapped.iter_mut() {
+        match pew_pos
             .iter()
-            .any(|(pew, _, _)| (*pew - *translation).length() < ZAP_DIST)
+            .find_map(|(from, to, _, _, _)| Some((*from, *to, pew_hits(*from, *to, *translation)?)))
         {
-            commands.entity(entity).remove::<BeingZapped>();
+            Some((from, to, hit)) => {
+                zapped.pos = impact_pos(from, to, &hit);
+                zapped.normal = hit.normal;
+            }
+            None => {
+                commands.entity(entity).remove::<BeingZapped>();
+            }
         }
     }
 }
@@ -61,3 +108,26 @@ pub fn apply_zap_damage(time: Res<Time>, mut query: Query<&mut HealthPoints, Wit
         health_points.health -= (time.delta_seconds() * 120.0) as i32;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impact_at_time_of_impact() {
+        let from = Vec3::new(0.0, 0.0, 1.0);
+        let to = Vec3::new(100.0, 0.0, 1.0);
+        let target = Vec3::new(50.0, 0.0, 0.0);
+        let hit = pew_hits(from, to, target).unwrap();
+        let pos = impact_pos(from, to, &hit);
+        assert!(
+            (pos.x - (50.0 - tune::PEW_ZAP_DISTANCE)).abs() < 1e-3,
+            "{:?}",
+            pos
+        );
+        assert_eq!(pos.z, 1.0);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+        // passing by at a distance
+        assert!(pew_hits(from, to, target + Vec3::Y * (tune::PEW_ZAP_DISTANCE + 1.0)).is_none());
+    }
+}
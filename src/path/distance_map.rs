@@ -0,0 +1,137 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use bevy::prelude::*;
+
+use super::grid::{steps, tune};
+use crate::hex::{Cube, CUBE_DIRECTIONS};
+
+// travel costs from start to every hex that can be reached for at most max_cost (Dijkstra that stops at
+// max_cost). Costs are the same as for grid::find_path.
+pub struct DistanceMap {
+    pub start: Cube,
+    costs: HashMap<Cube, f32>,
+}
+
+impl DistanceMap {
+    pub fn new(start: Cube, max_cost: f32, cost: impl Fn(Cube) -> Option<f32>) -> DistanceMap {
+        let max_cost = (max_cost * tune::COST_SCALE) as u32;
+        let mut costs = HashMap::new();
+        // smallest total first. Cube is not Ord, so it goes in as x and z.
+        let mut open = BinaryHeap::new();
+        if cost(start).is_some() {
+            open.push(Reverse((0, start.x, start.z)));
+        }
+        while let Some(Reverse((total, x, z))) = open.pop() {
+            let cube = Cube::new(x, -x - z, z);
+            if costs.contains_key(&cube) {
+                continue;
+            }
+            costs.insert(cube, total as f32 / tune::COST_SCALE);
+            for (next, step) in steps(cube, &cost) {
+                let next_total = total + step;
+                if next_total <= max_cost && !costs.contains_key(&next) {
+                    open.push(Reverse((next_total, next.x, next.z)));
+                }
+            }
+        }
+        DistanceMap { start, costs }
+    }
+
+    // None: not reachable within max_cost
+    pub fn cost(&self, cube: Cube) -> Option<f32> {
+        self.costs.get(&cube).copied()
+    }
+
+    // travel time at walk_speed, see cost_to_seconds
+    pub fn seconds(&self, cube: Cube, tile_size: Vec2, walk_speed: f32) -> Option<f32> {
+        self.cost(cube)
+            .map(|cost| cost_to_seconds(cost, tile_size, walk_speed))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Cube, f32)> + '_ {
+        self.costs.iter().map(|(cube, cost)| (*cube, *cost))
+    }
+
+    // whether the map may be different after these hexes changed: hexes in the map can get slower or blocked,
+    // hexes next to it can open a shorter way or extend it
+    pub fn touches(&self, changed: &HashSet<Cube>) -> bool {
+        changed.iter().any(|cube| {
+            self.costs.contains_key(cube)
+                || CUBE_DIRECTIONS
+                    .iter()
+                    .any(|dir| self.costs.contains_key(&(*cube + *dir)))
+        })
+    }
+}
+
+// a travel cost of 1.0 is one hex of plain ground, walked at walk_speed
+pub fn cost_to_seconds(cost: f32, tile_size: Vec2, walk_speed: f32) -> f32 {
+    cost * tile_size.x / walk_speed
+}
+
+pub fn seconds_to_cost(seconds: f32, tile_size: Vec2, walk_speed: f32) -> f32 {
+    seconds * walk_speed / tile_size.x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::grid::{find_path, parse_map};
+
+    #[test]
+    fn bounded_by_cost() {
+        #[rustfmt::skip]
+        let (costs, _, _) = parse_map(&[
+            ".........",
+            "..~~#....",
+            "..~~#..#.",
+            "....#..#.",
+            ".........",
+        ]);
+        let start = Cube::from_odd_r(Vec2::new(0.0, 2.0));
+        let cost = |cube: Cube| costs.get(&cube).copied();
+        let map = DistanceMap::new(start, 5.0, cost);
+        assert_eq!(map.cost(start), Some(0.0));
+        for goal in costs.keys() {
+            let (_, path_cost) = find_path(start, *goal, cost).unwrap();
+            if path_cost <= 5.0 {
+                assert_eq!(map.cost(*goal), Some(path_cost), "{:?}", goal);
+            } else {
+                assert_eq!(map.cost(*goal), None, "{:?}", goal);
+            }
+        }
+        // a walk of one hex: the start and its neighbours, without the wall
+        let map = DistanceMap::new(start, 1.0, cost);
+        assert_eq!(map.iter().count(), 4);
+        assert!(map.iter().all(|(cube, _)| costs.contains_key(&cube)));
+    }
+
+    #[test]
+    fn unreachable() {
+        let (costs, _, _) = parse_map(&["..#.."]);
+        let cost = |cube: Cube| costs.get(&cube).copied();
+        let start = Cube::from_odd_r(Vec2::new(0.0, 0.0));
+        let map = DistanceMap::new(start, 100.0, cost);
+        assert_eq!(map.iter().count(), 2);
+        assert_eq!(map.cost(Cube::from_odd_r(Vec2::new(3.0, 0.0))), None);
+        // start inside a wall
+        let wall = Cube::from_odd_r(Vec2::new(2.0, 0.0));
+        assert_eq!(DistanceMap::new(wall, 100.0, cost).iter().count(), 0);
+    }
+
+    #[test]
+    fn touches_changes_in_and_next_to_the_map() {
+        let (costs, _, _) = parse_map(&["........."]);
+        let cost = |cube: Cube| costs.get(&cube).copied();
+        let at = |x: f32| Cube::from_odd_r(Vec2::new(x, 0.0));
+        let map = DistanceMap::new(at(0.0), 2.0, cost);
+        assert_eq!(map.iter().count(), 3);
+        assert!(map.touches(&HashSet::from([at(1.0)])));
+        assert!(map.touches(&HashSet::from([at(3.0)])));
+        assert!(!map.touches(&HashSet::from([at(4.0), at(8.0)])));
+        assert!(!map.touches(&HashSet::new()));
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use pathfinding::prelude::dijkstra_all;
+
+use super::grid::{steps, tune};
+use crate::hex::Cube;
+
+// Dijkstra from the goal over the whole hex grid. Every reachable hex knows the next hex on a cheapest path
+// towards the goal, so any number of agents with the same goal can share one search.
+// Costs are the same as for grid::find_path (steps are symmetric, so the tree from the goal works both ways).
+pub struct FlowField {
+    pub goal: Cube,
+    // next hex towards the goal and the remaining cost, for every reachable hex except the goal
+    next: HashMap<Cube, (Cube, u32)>,
+}
+
+impl FlowField {
+    pub fn new(goal: Cube, cost: impl Fn(Cube) -> Option<f32>) -> Option<FlowField> {
+        cost(goal)?;
+        let next = dijkstra_all(&goal, |cube| steps(*cube, &cost));
+        Some(FlowField { goal, next })
+    }
+
+    // where to go from cube. None at the goal and in hexes that cannot reach it
+    pub fn next(&self, cube: Cube) -> Option<Cube> {
+        self.next.get(&cube).map(|(next, _)| *next)
+    }
+
+    // cost of the way from cube to the goal
+    pub fn cost(&self, cube: Cube) -> Option<f32> {
+        if cube == self.goal {
+            return Some(0.0);
+        }
+        self.next
+            .get(&cube)
+            .map(|(_, cost)| *cost as f32 / tune::COST_SCALE)
+    }
+
+    // follows the field from start. Same result as grid::find_path (up to ties between equally cheap paths)
+    pub fn path(&self, start: Cube) -> Option<Vec<Cube>> {
+        let mut path = vec![start];
+        let mut cube = start;
+        while cube != self.goal {
+            cube = self.next(cube)?;
+            path.push(cube);
+        }
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec2;
+
+    use super::*;
+    use crate::path::grid::{find_path, parse_map};
+
+    #[test]
+    fn same_cost_as_astar() {
+        #[rustfmt::skip]
+        let (costs, _, _) = parse_map(&[
+            ".......",
+            ".~~#~..",
+            "..~#~~.",
+            ".~~#...",
+            ".......",
+        ]);
+        let goal = Cube::from_odd_r(Vec2::new(6.0, 2.0));
+        let field = FlowField::new(goal, |cube| costs.get(&cube).copied()).unwrap();
+        for start in costs.keys() {
+            let (_, cost) = find_path(*start, goal, |cube| costs.get(&cube).copied()).unwrap();
+            assert_eq!(field.cost(*start), Some(cost), "{:?}", start);
+            let path = field.path(*start).unwrap();
+            for step in path.windows(2) {
+                assert_eq!(step[0].distance(step[1]), 1);
+                assert!(costs.contains_key(&step[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let (costs, _, _) = parse_map(&["..#.."]);
+        let goal = Cube::from_odd_r(Vec2::new(0.0, 0.0));
+        let field = FlowField::new(goal, |cube| costs.get(&cube).copied()).unwrap();
+        let behind_wall = Cube::from_odd_r(Vec2::new(4.0, 0.0));
+        assert_eq!(field.next(behind_wall), None);
+        assert_eq!(field.cost(behind_wall), None);
+        assert_eq!(field.next(goal), None);
+        // goal inside a wall
+        let wall = Cube::from_odd_r(Vec2::new(2.0, 0.0));
+        assert!(FlowField::new(wall, |cube| costs.get(&cube).copied()).is_none());
+    }
+}
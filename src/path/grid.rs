@@ -0,0 +1,266 @@
+#[cfg(test)]
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use pathfinding::prelude::astar;
+
+use crate::hex::{collision::hex_center, Cube, CUBE_DIRECTIONS};
+
+pub(super) mod tune {
+    // path costs are fractional, but astar wants integers
+    pub const COST_SCALE: f32 = 1000.0;
+    // how far (in hexes) positions inside walls are moved to the next walkable hex
+    pub const SNAP_RADIUS: i32 = 4;
+    // walkable hexes around the goal that are tried when the closest one cannot be reached
+    pub const MAX_GOAL_CANDIDATES: usize = 3;
+}
+
+// the hexes that can be entered from cube and the (scaled) cost of the step. A step costs the average of the two
+// hexes it connects (half of the way is on each).
+pub(super) fn steps(cube: Cube, cost: &impl Fn(Cube) -> Option<f32>) -> Vec<(Cube, u32)> {
+    let from = cost(cube).unwrap_or(1.0);
+    CUBE_DIRECTIONS
+        .iter()
+        .filter_map(|dir| {
+            let next = cube + *dir;
+            let to = cost(next)?;
+            Some((next, ((from + to) * 0.5 * tune::COST_SCALE) as u32))
+        })
+        .collect()
+}
+
+// A* over the hex grid. cost returns the travel cost of a hex (see TerrainInfo::travel_cost, >= 1.0) or None
+// if it cannot be entered. A step costs at least 1.0 (see steps), so the hex distance is an admissible heuristic.
+// Returns the hexes from start to goal (both included) and the total cost.
+pub fn find_path(
+    start: Cube,
+    goal: Cube,
+    cost: impl Fn(Cube) -> Option<f32>,
+) -> Option<(Vec<Cube>, f32)> {
+    cost(start)?;
+    cost(goal)?;
+    let (path, total) = astar(
+        &start,
+        |cube| steps(*cube, &cost),
+        |cube| cube.distance(goal) as u32 * tune::COST_SCALE as u32,
+        |cube| *cube == goal,
+    )?;
+    Some((path, total as f32 / tune::COST_SCALE))
+}
+
+// hexes with a node (see is_node) around pos (2D world position), closest first. Searches ring by ring around
+// the hex that contains pos, up to max_radius steps away. Once a ring has a node, the next one is searched
+// as well: its hexes can still be closer to pos than the farther ones of the ring before.
+pub fn nodes_near(
+    pos: Vec2,
+    tile_size: Vec2,
+    max_radius: i32,
+    is_node: impl Fn(Cube) -> bool,
+) -> Vec<Cube> {
+    let center = Cube::from_odd_r_screen((pos + tile_size * 0.5) / tile_size);
+    let mut nodes = Vec::new();
+    let mut last_radius = max_radius;
+    for radius in 0..=max_radius {
+        if radius > last_radius {
+            break;
+        }
+        let len = nodes.len();
+        nodes.extend(
+            center
+                .ring(radius)
+                .into_iter()
+                .filter(|cube| is_node(*cube)),
+        );
+        if len == 0 && !nodes.is_empty() {
+            last_radius = radius + 1;
+        }
+    }
+    nodes.sort_by(|a, b| {
+        let da = hex_center(*a, tile_size).distance_squared(pos);
+        let db = hex_center(*b, tile_size).distance_squared(pos);
+        da.total_cmp(&db)
+    });
+    nodes
+}
+
+pub fn nearest_node(
+    pos: Vec2,
+    tile_size: Vec2,
+    max_radius: i32,
+    is_node: impl Fn(Cube) -> bool,
+) -> Option<Cube> {
+    nodes_near(pos, tile_size, max_radius, is_node)
+        .first()
+        .copied()
+}
+
+// hand drawn maps for the path tests in odd-r layout, one character per hex: '#' wall, '.' ground, '~' water
+// (cost 2), 'S' / 'G' start / goal on ground. Rows go down the screen, but that does not matter here.
+// Returns the travel costs and start and goal (if the map has them).
+#[cfg(test)]
+pub(crate) fn parse_map(map: &[&str]) -> (HashMap<Cube, f32>, Option<Cube>, Option<Cube>) {
+    let mut costs = HashMap::new();
+    let mut start = None;
+    let mut goal = None;
+    for (y, row) in map.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            let cube = Cube::from_odd_r(Vec2::new(x as f32, y as f32));
+            match c {
+                '.' => (),
+                '~' => {
+                    costs.insert(cube, 2.0);
+                    continue;
+                }
+                'S' => start = Some(cube),
+                'G' => goal = Some(cube),
+                _ => continue,
+            }
+            costs.insert(cube, 1.0);
+        }
+    }
+    (costs, start, goal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(map: &[&str]) -> Option<(Vec<Cube>, f32)> {
+        let (costs, start, goal) = parse_map(map);
+        find_path(start.unwrap(), goal.unwrap(), |cube| {
+            costs.get(&cube).copied()
+        })
+    }
+
+    fn assert_connected(path: &[Cube]) {
+        for step in path.windows(2) {
+            assert_eq!(step[0].distance(step[1]), 1, "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn straight_line() {
+        let (path, cost) = run(&["S....G"]).unwrap();
+        assert_eq!(path.len(), 6);
+        assert_eq!(cost, 5.0);
+        assert_connected(&path);
+    }
+
+    #[test]
+    fn around_wall() {
+        #[rustfmt::skip]
+        let map = [
+            "......",
+            "..#...",
+            "S.#.G.",
+            "..#...",
+            "......",
+        ];
+        let (path, cost) = run(&map).unwrap();
+        assert_connected(&path);
+        let (costs, _, _) = parse_map(&map);
+        assert!(path.iter().all(|cube| costs.contains_key(cube)));
+        // detour around the wall: two steps longer than the direct line
+        assert_eq!(path.len(), 7, "{:?}", path);
+        assert_eq!(cost, 6.0);
+    }
+
+    #[test]
+    fn blocked() {
+        #[rustfmt::skip]
+        let map = [
+            "..#...",
+            ".#..#.",
+            "S#..#G",
+            ".#..#.",
+            "..#...",
+        ];
+        assert!(run(&map).is_none());
+        // goal inside a wall
+        let (costs, start, _) = parse_map(&["S.#G"]);
+        let wall = Cube::from_odd_r(Vec2::new(2.0, 0.0));
+        assert!(find_path(start.unwrap(), wall, |cube| costs.get(&cube).copied()).is_none());
+    }
+
+    #[test]
+    fn avoids_expensive_terrain() {
+        // straight through the water costs 9, the way around is 8 steps on ground
+        #[rustfmt::skip]
+        let map = [
+            ".......",
+            ".~~~~..",
+            "S~~~~G.",
+            ".~~~~..",
+            ".......",
+        ];
+        let (path, cost) = run(&map).unwrap();
+        assert_connected(&path);
+        assert_eq!(cost, 8.0, "{:?}", path);
+        let row = |cube: &Cube| cube.to_odd_r().y as i32;
+        assert!(path.iter().all(|cube| row(cube) != 2
+            || cube == path.first().unwrap()
+            || cube == path.last().unwrap()));
+
+        // but not if the detour is too long
+        #[rustfmt::skip]
+        let map = [
+            ".......",
+            "#~~~~##",
+            "S~~~~G#",
+            "#~~~~##",
+            ".......",
+        ];
+        let (path, cost) = run(&map).unwrap();
+        assert_connected(&path);
+        assert!(path.len() < 8, "{:?}", path);
+        assert_eq!(cost, 1.5 + 3.0 * 2.0 + 1.5);
+    }
+
+    #[test]
+    fn ring() {
+        let center = Cube::new(1, -3, 2);
+        for radius in 0..4 {
+            let ring = center.ring(radius);
+            assert_eq!(ring.len(), (radius as usize * 6).max(1));
+            assert!(ring.iter().all(|cube| cube.distance(center) == radius));
+            assert!(ring.iter().all(|cube| cube.x + cube.y + cube.z == 0));
+            for (i, a) in ring.iter().enumerate() {
+                assert!(!ring[i + 1..].contains(a));
+            }
+        }
+    }
+
+    #[test]
+    fn nearest() {
+        let tile_size = Vec2::new(18.0, 20.0);
+        #[rustfmt::skip]
+        let (costs, start, goal) = parse_map(&[
+            "S.......",
+            "..####..",
+            "..####G.",
+            "..####..",
+        ]);
+        let (start, goal) = (start.unwrap(), goal.unwrap());
+        let is_node = |cube| costs.contains_key(&cube);
+        // on a node
+        let pos = hex_center(start, tile_size) + Vec2::new(2.0, -3.0);
+        assert_eq!(nearest_node(pos, tile_size, 4, is_node), Some(start));
+        // inside the wall, right next to the goal
+        let wall = Cube::from_odd_r(Vec2::new(5.0, 2.0));
+        let pos = hex_center(wall, tile_size) + Vec2::new(6.0, 0.0);
+        assert_eq!(nearest_node(pos, tile_size, 4, is_node), Some(goal));
+        let near = nodes_near(pos, tile_size, 4, is_node);
+        assert!(near.iter().all(|cube| is_node(*cube)));
+        for pair in near.windows(2) {
+            assert!(
+                hex_center(pair[0], tile_size).distance(pos)
+                    <= hex_center(pair[1], tile_size).distance(pos)
+            );
+        }
+        // deep inside: nothing within one step
+        let wall = Cube::from_odd_r(Vec2::new(3.0, 2.0));
+        let pos = hex_center(wall, tile_size);
+        assert_eq!(nearest_node(pos, tile_size, 1, is_node), None);
+        assert!(nearest_node(pos, tile_size, 2, is_node).is_some());
+    }
+}
@@ -1,20 +1,38 @@
-use std::sync::Mutex;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
 use bevy_prototype_debug_lines::DebugLines;
-use pathfinding::prelude::*;
-use petgraph::graphmap::UnGraphMap;
+
+pub mod distance_map;
+pub mod flow_field;
+pub mod grid;
+pub mod smooth;
+pub mod tasks;
 
 use crate::{
     debug::{debug_draw_cross, debug_draw_line},
+    hex::{collision::sweep_circle, tilemap::Resources, Cube, CUBE_DIRECTIONS},
     movement::{crab_controller::CrabFollowPath, zap::Zappable},
-    InputTarget,
+    state::AppState,
+    tune, InputTarget, TargetFlag,
+};
+
+use self::{
+    distance_map::{seconds_to_cost, DistanceMap},
+    flow_field::FlowField,
 };
 
 #[derive(Component)]
 pub struct Waypoint;
 
+// travel cost multiplier of the terrain at the waypoint (1.0 if missing)
+#[derive(Component)]
+pub struct WaypointCost(pub f32);
+
 fn _debug_draw_system(
     mut debug_lines: ResMut<DebugLines>,
     query: Query<&Transform, With<Waypoint>>,
@@ -25,203 +43,321 @@ fn _debug_draw_system(
     }
 }
 
+// sent when nodes of the WaypointGraph were added, removed or changed their cost
+pub struct WaypointGraphChanged {
+    pub cubes: Vec<Cube>,
+}
+
+// runs in PostUpdate so that it sees waypoints despawned by the commands of Update (tile changes, chunk unloading)
+#[allow(clippy::type_complexity)]
 fn update_graph_system(
     mut debug_lines: ResMut<DebugLines>,
+    resources: Res<Resources>,
     mut graph: ResMut<WaypointGraph>,
-    query: Query<(Entity, &Transform), With<Waypoint>>,
-    added: Query<Entity, Added<Waypoint>>,
+    query: Query<
+        (Entity, &Transform, Option<&WaypointCost>),
+        (With<Waypoint>, Or<(Added<Waypoint>, Changed<WaypointCost>)>),
+    >,
+    removed: RemovedComponents<Waypoint>,
+    mut changed_events: EventWriter<WaypointGraphChanged>,
 ) {
-    use rtriangulate::{triangulate, TriangulationPoint};
+    let mut changed = Vec::new();
 
-    if added.is_empty() {
-        return;
+    for entity in removed.iter() {
+        if let Some(cube) = graph.cubes.remove(&entity) {
+            // the hex may already belong to a newer waypoint
+            if graph
+                .nodes
+                .get(&cube)
+                .is_some_and(|node| node.entity == entity)
+            {
+                graph.nodes.remove(&cube);
+            }
+            changed.push(cube);
+        }
     }
 
-    info!("waypoint added");
+    for (entity, transform, cost) in query.iter() {
+        let cube = resources.world_to_cube(transform.translation);
+        let node = WaypointNode {
+            entity,
+            cost: cost.map_or(1.0, |WaypointCost(cost)| *cost),
+        };
+        if let Some(old_node) = graph.nodes.insert(cube, node) {
+            if old_node.entity != entity {
+                graph.cubes.remove(&old_node.entity);
+            }
+        }
+        graph.cubes.insert(entity, cube);
+        changed.push(cube);
 
-    let entities_and_points = query
-        .iter()
-        .map(|(entity, transform)| (entity, transform.translation))
-        .collect::<Vec<_>>();
+        // one node per hex, neighbouring hexes are connected
+        for dir in CUBE_DIRECTIONS.iter() {
+            let neighbor = cube + *dir;
+            if graph.nodes.contains_key(&neighbor) {
+                debug_draw_line(
+                    &mut debug_lines,
+                    resources.cube_to_world(cube),
+                    resources.cube_to_world(neighbor),
+                    Some(5.0),
+                );
+            }
+        }
+    }
 
-    let triangulate_points = entities_and_points
+    if !changed.is_empty() {
+        graph.costs = None;
+        debug!(
+            "waypoint graph: {} nodes changed, {} total",
+            changed.len(),
+            graph.nodes.len()
+        );
+        changed_events.send(WaypointGraphChanged { cubes: changed });
+    }
+}
+
+// paths that still have to cross (or touch) a changed hex may be blocked or no longer the cheapest: plan them again from
+// the current position to the old destination
+fn replan_paths_system(
+    mut commands: Commands,
+    resources: Res<Resources>,
+    mut changed_events: EventReader<WaypointGraphChanged>,
+    query: Query<(Entity, &Transform, &CrabFollowPath, &WaypointPath), Without<PathQuery>>,
+) {
+    let changed = changed_events
         .iter()
-        .map(|(_entity, translation)| TriangulationPoint::new(translation.x, translation.y))
-        .collect::<Vec<_>>();
-
-    graph.graph_map.clear();
-    if let Ok(triangles) = triangulate(&triangulate_points) {
-        for triangle in triangles {
-            for (istart, iend) in [
-                (triangle.0, triangle.1),
-                (triangle.1, triangle.2),
-                (triangle.2, triangle.0),
-            ] {
-                let (start_entity, start) = entities_and_points[istart];
-                let (end_entity, end) = entities_and_points[iend];
-                let d = (start - end).length();
-                if d > 18.0 {
-                    continue;
-                }
-                debug_draw_line(&mut debug_lines, start, end, Some(5.0));
-                graph.graph_map.add_edge(start_entity, end_entity, 1.0f32);
-            }
+        .flat_map(|event| event.cubes.iter().copied())
+        .collect::<HashSet<_>>();
+    if changed.is_empty() {
+        return;
+    }
+
+    for (entity, transform, follow_path, WaypointPath { waypoints }) in query.iter() {
+        let end = match waypoints.last() {
+            Some(end) => *end,
+            None => continue,
+        };
+        // the waypoints are only the corners of the path, check the way in between as well
+        let remaining = std::iter::once(transform.translation)
+            .chain(waypoints.iter().skip(follow_path.next_step).copied())
+            .map(|p| p.truncate())
+            .collect::<Vec<_>>();
+        let crosses_changed = remaining
+            .iter()
+            .any(|p| changed.contains(&resources.world_to_cube(p.extend(0.0))))
+            || remaining.windows(2).any(|segment| {
+                sweep_circle(
+                    segment[0],
+                    segment[1] - segment[0],
+                    tune::WALKER_RADIUS,
+                    resources.tile_size,
+                    |cube| changed.contains(&cube),
+                )
+                .is_some()
+            });
+        if !crosses_changed {
+            continue;
         }
+        debug!("replan path of {:?}", entity);
+        commands
+            .entity(entity)
+            .remove::<WaypointPath>()
+            .insert(PathQuery {
+                start: transform.translation,
+                end,
+                // already on the way, possibly into a wall
+                priority: PathPriority::High,
+            })
+            .insert(CrabFollowPath::default());
     }
-    info!("{:?}", graph.graph_map);
-    // for transform in query.iter() {}
 }
 
+// the search runs in the background (see tasks.rs). Its result is a WaypointPath (PathStatus::Found,
+// PathFound) or PathStatus::Failed and PathFailed. Removing the PathQuery (or inserting a new one) cancels the
+// search.
 #[derive(Component)]
 #[component(storage = "SparseSet")]
 pub struct PathQuery {
     pub start: Vec3,
     pub end: Vec3,
+    pub priority: PathPriority,
+}
+
+// higher priorities are searched first when there is more to do than fits into the frame budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum PathPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+// state of the last path search of an entity. Movement controls that do not search (MovementFollowTarget) use
+// it as well to tell that there is no way.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStatus {
+    Searching,
+    Found,
+    Failed,
+}
+
+pub struct PathFound {
+    pub entity: Entity,
+}
+
+pub struct PathFailed {
+    pub entity: Entity,
 }
 
+// world positions of the hexes along the path where it changes direction (see smooth::string_pull)
 #[derive(Component, Debug, Reflect)]
 pub struct WaypointPath {
-    pub waypoints: Vec<Entity>,
+    pub waypoints: Vec<Vec3>,
 }
 
-// TODO: check if this is a useful pattern: spawn path finding as it's own entity (to make async path finding easier),
-// and then have the finished path attached to a target entity (that would then react to this according to AI decisions).
-fn _find_path_system(
-    mut commands: Commands,
-    graph: Res<WaypointGraph>,
-    query: Query<(Entity, &PathQuery), Added<PathQuery>>,
-    waypoint_query: Query<(Entity, &Transform), With<Waypoint>>,
+fn _print_new_path_system(
+    query: Query<&WaypointPath, Added<WaypointPath>>,
+    mut debug_lines: ResMut<DebugLines>,
 ) {
-    let start = bevy::utils::Instant::now();
-    for (path_query_entity, path_query) in query.iter() {
-        let mut start_entity = (f32::MAX, None);
-        let mut end_entity = (f32::MAX, None);
-
-        for (waypoint_entity, Transform { translation, .. }) in waypoint_query.iter() {
-            let dstart = (*translation - path_query.start).length();
-            let dend = (*translation - path_query.end).length();
-            if dstart < start_entity.0 {
-                start_entity = (dstart, Some(waypoint_entity));
-            }
-            if dend < end_entity.0 {
-                end_entity = (dend, Some(waypoint_entity));
-            }
-        }
-        if let ((_, Some(start_entity)), (_, Some(end_entity))) = (start_entity, end_entity) {
-            // pathfinding::directed::astar::
-            let res = astar(
-                &start_entity,
-                |e| graph.graph_map.neighbors(*e).map(|e| (e, 1)),
-                |_e| 1, // NOTE: heuristic missing (parallel version has it)
-                |e| *e == end_entity,
-            );
-            if let Some(res) = res {
-                commands
-                    .entity(path_query_entity)
-                    .insert(WaypointPath { waypoints: res.0 });
-            }
+    for path in query.iter() {
+        info!("path: {:?}", path);
+
+        for p in path.waypoints.windows(2) {
+            let offs = Vec3::new(0.0, 1.0, 0.0);
+            debug_draw_line(&mut debug_lines, p[0] + offs, p[1] + offs, Some(10.0));
         }
-        commands.entity(path_query_entity).despawn();
-    }
-    if !query.is_empty() {
-        info!("path find: {:?}", start.elapsed());
     }
 }
 
-// TODO: check if this is a useful pattern: spawn path finding as it's own entity (to make async path finding easier),
-// and then have the finished path attached to a target entity (that would then react to this according to AI decisions).
-fn find_path_system_par(
-    mut commands: Commands,
+struct WaypointNode {
+    entity: Entity,
+    cost: f32,
+}
+
+// walkable hexes (the ones with a waypoint) and their travel cost. Kept up to date incrementally by
+// update_graph_system.
+#[derive(Default)]
+pub struct WaypointGraph {
+    nodes: HashMap<Cube, WaypointNode>,
+    // where each waypoint went, to find its node again once it is despawned
+    cubes: HashMap<Entity, Cube>,
+    // see costs()
+    costs: Option<Arc<HashMap<Cube, f32>>>,
+}
+
+// shared by everything that chases the target (see MovementFollowTarget), instead of one path query each
+#[derive(Default)]
+pub struct TargetFlowField {
+    pub field: Option<FlowField>,
+    // counts the rebuilds, so followers that found no way know when to look again
+    pub generation: u32,
+}
+
+// rebuilt when the target moves to another hex or the graph changes
+fn update_flow_field_system(
+    resources: Res<Resources>,
     graph: Res<WaypointGraph>,
-    query: Query<(Entity, &PathQuery), Added<PathQuery>>,
-    waypoint_query: Query<(Entity, &Transform), With<Waypoint>>,
+    mut flow_field: ResMut<TargetFlowField>,
+    mut changed_events: EventReader<WaypointGraphChanged>,
+    target_query: Query<&Transform, With<TargetFlag>>,
 ) {
-    let out = Mutex::new(Vec::<(WaypointPath, Entity)>::new());
-    let start = bevy::utils::Instant::now();
-    // scatter: do actual path finding in parallel
-    query.par_for_each(/*&pool,*/ 16, |(path_query_entity, path_query)| {
-        let mut start_entity = (f32::MAX, None);
-        let mut end_entity = (f32::MAX, None);
-
-        for (waypoint_entity, Transform { translation, .. }) in waypoint_query.iter() {
-            let dstart = (*translation - path_query.start).length();
-            let dend = (*translation - path_query.end).length();
-            if dstart < start_entity.0 {
-                start_entity = (dstart, Some(waypoint_entity));
-            }
-            if dend < end_entity.0 {
-                end_entity = (dend, Some(waypoint_entity));
-            }
-        }
-        if let ((_, Some(start_entity)), (_, Some(end_entity))) = (start_entity, end_entity) {
-            let res = astar(
-                &start_entity,
-                |e| graph.graph_map.neighbors(*e).map(|e| (e, 1)),
-                |_e| {
-                    // FIXME: this might be stupid: heuristic is based on actual 'pixel distance' but successor function
-                    // always returns distance 1...
-                    waypoint_query
-                        .get(*_e)
-                        .map(|(_, Transform { translation, .. })| {
-                            (*translation - path_query.end).length() as i32
-                        })
-                        .unwrap_or(1)
-                },
-                |e| *e == end_entity,
-            );
-            if let Some(res) = res {
-                let waypoint_path = WaypointPath { waypoints: res.0 };
-                if let Ok(mut out) = out.lock() {
-                    out.push((waypoint_path, path_query_entity));
-                }
+    let graph_changed = changed_events.iter().count() > 0;
+    let goal = match target_query
+        .iter()
+        .next()
+        .and_then(|transform| graph.nearest(transform.translation, resources.tile_size))
+    {
+        Some(goal) => goal,
+        None => {
+            if flow_field.field.is_some() {
+                flow_field.field = None;
+                flow_field.generation += 1;
             }
+            return;
         }
-    });
-    if !query.is_empty() {
-        info!("path find (par): {:?}", start.elapsed());
-    }
-    // gather & distribute results to target entities (munching up the mutex along the way... sidenote: I love rust)
-    if let Ok(out) = out.into_inner() {
-        // info!("num = {}", out.len());
-        for (path, path_query_entity) in out {
-            commands.entity(path_query_entity).insert(path);
-            // cleanup path query in this loop as well. Not sure if this is better than iterating over query
-            // again, but less code is more good.
-            commands.entity(path_query_entity).remove::<PathQuery>();
-        }
+    };
+    let up_to_date = match &flow_field.field {
+        Some(field) => field.goal == goal,
+        None => false,
+    };
+    if up_to_date && !graph_changed {
+        return;
     }
+    let start = bevy::utils::Instant::now();
+    // None until the waypoints are there. Followers then go straight for the target.
+    flow_field.field = FlowField::new(goal, |cube| graph.nodes.get(&cube).map(|node| node.cost));
+    flow_field.generation += 1;
+    debug!("flow field: {:?}", start.elapsed());
+}
+
+// travel times from the position of the entity, see WaypointGraph::distance_map. Kept up to date by
+// update_travel_distances_system.
+#[derive(Component)]
+pub struct TravelDistances {
+    pub max_seconds: f32,
+    // None: not near the waypoint graph
+    pub map: Option<DistanceMap>,
+}
 
-    if !query.is_empty() {
-        info!("path find: {:?}", start.elapsed());
+impl TravelDistances {
+    pub fn new(max_seconds: f32) -> Self {
+        TravelDistances {
+            max_seconds,
+            map: None,
+        }
     }
 }
 
-fn _print_new_path_system(
-    query: Query<&WaypointPath, Added<WaypointPath>>,
-    waypoint_query: Query<&Transform, With<Waypoint>>,
-    mut debug_lines: ResMut<DebugLines>,
+// rebuilt when the entity moves to another hex or the graph changes in or next to the map
+pub fn update_travel_distances_system(
+    resources: Res<Resources>,
+    graph: Res<WaypointGraph>,
+    mut changed_events: EventReader<WaypointGraphChanged>,
+    mut query: Query<(&Transform, &mut TravelDistances)>,
 ) {
-    for path in query.iter() {
-        info!("path: {:?}", path);
-
-        for p in path.waypoints.windows(2) {
-            let start = waypoint_query.get(p[0]).unwrap();
-            let end = waypoint_query.get(p[1]).unwrap();
-
-            let offs = Vec3::new(0.0, 1.0, 0.0);
-            debug_draw_line(
-                &mut debug_lines,
-                start.translation + offs,
-                end.translation + offs,
-                Some(10.0),
-            );
+    let changed = changed_events
+        .iter()
+        .flat_map(|event| event.cubes.iter().copied())
+        .collect::<HashSet<_>>();
+    for (transform, mut distances) in query.iter_mut() {
+        let start = graph.nearest(transform.translation, resources.tile_size);
+        let up_to_date = match &distances.map {
+            Some(map) => Some(map.start) == start && !map.touches(&changed),
+            None => start.is_none(),
+        };
+        if up_to_date {
+            continue;
         }
+        distances.map = graph.distance_map(
+            transform.translation,
+            resources.tile_size,
+            distances.max_seconds,
+        );
     }
 }
 
-#[derive(Default)]
-struct WaypointGraph {
-    graph_map: UnGraphMap<Entity, f32>,
+impl WaypointGraph {
+    // the walkable hex closest to a world position, also for positions inside walls (if not too deep)
+    pub fn nearest(&self, pos: Vec3, tile_size: Vec2) -> Option<Cube> {
+        grid::nearest_node(pos.truncate(), tile_size, grid::tune::SNAP_RADIUS, |cube| {
+            self.nodes.contains_key(&cube)
+        })
+    }
+
+    // everything a walker at from can reach within max_seconds (at walk speed, slower on rough terrain).
+    // Look up other positions with nearest() and DistanceMap::seconds.
+    pub fn distance_map(
+        &self,
+        from: Vec3,
+        tile_size: Vec2,
+        max_seconds: f32,
+    ) -> Option<DistanceMap> {
+        let start = self.nearest(from, tile_size)?;
+        let max_cost = seconds_to_cost(max_seconds, tile_size, tune::WALK_SPEED);
+        Some(DistanceMap::new(start, max_cost, |cube| {
+            self.nodes.get(&cube).map(|node| node.cost)
+        }))
+    }
 }
 
 fn path_egui_ui_system(
@@ -276,6 +412,7 @@ fn path_egui_ui_system(
                     .insert(PathQuery {
                         start: *ferris_pos,
                         end: *player_pos,
+                        priority: PathPriority::Normal,
                     })
                     .insert(CrabFollowPath::default());
             }
@@ -288,10 +425,27 @@ pub struct PathPlugin;
 impl Plugin for PathPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<WaypointGraph>()
-            .add_system(path_egui_ui_system)
+            .init_resource::<TargetFlowField>()
+            .init_resource::<tasks::PathTasks>()
+            .add_event::<WaypointGraphChanged>()
+            .add_event::<PathFound>()
+            .add_event::<PathFailed>()
             // .add_system(debug_draw_system)
-            .add_system(update_graph_system)
-            .add_system(find_path_system_par)
+            .add_system_to_stage(CoreStage::PostUpdate, update_graph_system)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                replan_paths_system.after(update_graph_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Play)
+                    .with_system(path_egui_ui_system)
+                    .with_system(tasks::queue_path_queries_system)
+                    .with_system(
+                        tasks::run_path_tasks_system.after(tasks::queue_path_queries_system),
+                    )
+                    .with_system(update_flow_field_system)
+                    .with_system(update_travel_distances_system),
+            )
             // .add_system(print_new_path_system)
             ;
     }
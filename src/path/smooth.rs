@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+
+use crate::hex::{
+    collision::{hex_center, sweep_circle},
+    Cube,
+};
+
+mod tune {
+    // cost differences below this do not count as leaving the terrain of the path
+    pub const COST_EPSILON: f32 = 1e-3;
+}
+
+// string pulling on a path from grid::find_path: keeps only the hexes where the path has to turn. From each kept
+// hex the path goes straight to the farthest later hex that a circle of radius can reach without touching a
+// hex that cannot be entered. The center line must also stay on the skipped hexes or on terrain that is no more
+// expensive than the cheapest of them, so shortcuts do not lead through the water the path went around
+// (touching it on the way along the shore is fine).
+// Returns the centers of the kept hexes (2D, see collision::hex_center), including start and goal.
+pub fn string_pull(
+    path: &[Cube],
+    radius: f32,
+    tile_size: Vec2,
+    cost: impl Fn(Cube) -> Option<f32>,
+) -> Vec<Vec2> {
+    let center = |cube: Cube| hex_center(cube, tile_size);
+    let mut out = Vec::new();
+    let mut anchor = 0;
+    while anchor < path.len() {
+        out.push(center(path[anchor]));
+        let mut next = anchor + 1;
+        let mut min_cost = cost(path[anchor]).unwrap_or(1.0);
+        // the direct neighbour is always reachable, look for something farther away
+        for candidate in anchor + 1..path.len() {
+            min_cost = min_cost.min(cost(path[candidate]).unwrap_or(1.0));
+            let skipped = &path[anchor..=candidate];
+            let from = center(path[anchor]);
+            let to = center(path[candidate]);
+            let blocked = sweep_circle(from, to - from, radius, tile_size, |cube| {
+                cost(cube).is_none()
+            })
+            .is_some()
+                || sweep_circle(from, to - from, 0.0, tile_size, |cube| {
+                    !skipped.contains(&cube)
+                        && cost(cube).is_some_and(|cost| cost > min_cost + tune::COST_EPSILON)
+                })
+                .is_some();
+            if blocked {
+                break;
+            }
+            next = candidate;
+        }
+        anchor = next;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::path::grid::{find_path, parse_map};
+
+    const TILE_SIZE: Vec2 = Vec2::new(18.0, 20.0);
+    const RADIUS: f32 = 3.0;
+
+    // map in the notation of grid::parse_map
+    fn run(map: &[&str]) -> (Vec<Cube>, Vec<Vec2>, HashMap<Cube, f32>) {
+        let (costs, start, goal) = parse_map(map);
+        let cost = |cube: Cube| costs.get(&cube).copied();
+        let (path, _) = find_path(start.unwrap(), goal.unwrap(), cost).unwrap();
+        let smooth = string_pull(&path, RADIUS, TILE_SIZE, cost);
+        (path, smooth, costs)
+    }
+
+    fn assert_clear(smooth: &[Vec2], radius: f32, costs: &HashMap<Cube, f32>) {
+        for segment in smooth.windows(2) {
+            let hit = sweep_circle(
+                segment[0],
+                segment[1] - segment[0],
+                radius,
+                TILE_SIZE,
+                |cube| !costs.contains_key(&cube),
+            );
+            assert!(hit.is_none(), "{:?} {:?}", segment, hit);
+        }
+    }
+
+    #[test]
+    fn open_field_is_straight() {
+        #[rustfmt::skip]
+        let (path, smooth, costs) = run(&[
+            "......",
+            "S.....",
+            "......",
+            "......",
+            ".....G",
+            "......",
+        ]);
+        assert!(path.len() > 2);
+        assert_eq!(smooth.len(), 2, "{:?}", smooth);
+        assert_eq!(smooth[0], hex_center(path[0], TILE_SIZE));
+        assert_eq!(smooth[1], hex_center(*path.last().unwrap(), TILE_SIZE));
+        assert_clear(&smooth, RADIUS, &costs);
+    }
+
+    #[test]
+    fn turns_at_wall() {
+        #[rustfmt::skip]
+        let (path, smooth, costs) = run(&[
+            "S.#....",
+            "..#....",
+            "..#.#..",
+            "....#.G",
+        ]);
+        assert!(smooth.len() > 2, "{:?}", smooth);
+        assert!(smooth.len() < path.len(), "{:?}", smooth);
+        assert_clear(&smooth, RADIUS, &costs);
+    }
+
+    #[test]
+    fn no_shortcut_through_water() {
+        #[rustfmt::skip]
+        let (path, smooth, costs) = run(&[
+            "........",
+            ".~~~~~~.",
+            "S~~~~~~G",
+            ".~~~~~~.",
+            "........",
+        ]);
+        assert!(smooth.len() > 2, "{:?}", smooth);
+        // the segments go along the shore, but only into water the path went through anyway
+        let dry = costs
+            .iter()
+            .filter(|(cube, cost)| **cost <= 1.0 || path.contains(cube))
+            .map(|(cube, cost)| (*cube, *cost))
+            .collect::<HashMap<_, _>>();
+        assert_clear(&smooth, RADIUS, &costs);
+        assert_clear(&smooth, 0.0, &dry);
+    }
+}
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, sync::Arc};
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+    utils::Instant,
+};
+use futures_lite::future;
+
+use super::{
+    grid, smooth, PathFailed, PathFound, PathPriority, PathQuery, PathStatus, WaypointGraph,
+    WaypointPath,
+};
+use crate::hex::{tilemap::Resources, Cube};
+
+mod tune {
+    // search time (summed over all threads) that may be spent per frame on average. Expensive searches are
+    // paid back over the next frames, so the cheap ones do not starve.
+    pub const FRAME_BUDGET: f32 = 0.002;
+    // searches running at the same time
+    pub const MAX_RUNNING: usize = 8;
+}
+
+struct Request {
+    entity: Entity,
+    start: Vec3,
+    end: Vec3,
+    priority: PathPriority,
+    // first come, first served within the same priority
+    seq: u64,
+}
+
+// the waypoints (None: no path) and how long the search took
+type PathTask = Task<(Option<Vec<Vec3>>, f32)>;
+
+// PathQuerys waiting for or being worked on by a search on the AsyncComputeTaskPool. There is at most one
+// request per entity: a new PathQuery replaces the old one, and the request is dropped when the PathQuery
+// (or the whole entity) goes away.
+#[derive(Default)]
+pub struct PathTasks {
+    queue: Vec<Request>,
+    running: HashMap<Entity, PathTask>,
+    // seconds of search time that may still be spent, negative after expensive searches
+    budget: f32,
+    seq: u64,
+}
+
+pub(super) fn queue_path_queries_system(
+    mut commands: Commands,
+    mut tasks: ResMut<PathTasks>,
+    query: Query<(Entity, &PathQuery), Changed<PathQuery>>,
+) {
+    for (entity, path_query) in query.iter() {
+        // superseded: the old search is cancelled by dropping its task
+        tasks.queue.retain(|request| request.entity != entity);
+        tasks.running.remove(&entity);
+        // the old path leads somewhere else
+        commands
+            .entity(entity)
+            .remove::<WaypointPath>()
+            .insert(PathStatus::Searching);
+
+        tasks.seq += 1;
+        let seq = tasks.seq;
+        tasks.queue.push(Request {
+            entity,
+            start: path_query.start,
+            end: path_query.end,
+            priority: path_query.priority,
+            seq,
+        });
+    }
+}
+
+pub(super) fn run_path_tasks_system(
+    mut commands: Commands,
+    resources: Res<Resources>,
+    mut graph: ResMut<WaypointGraph>,
+    mut tasks: ResMut<PathTasks>,
+    query: Query<(), With<PathQuery>>,
+    mut found_events: EventWriter<PathFound>,
+    mut failed_events: EventWriter<PathFailed>,
+) {
+    let tasks = &mut *tasks;
+    // cancel searches nobody waits for anymore
+    tasks
+        .queue
+        .retain(|request| query.get(request.entity).is_ok());
+    tasks.running.retain(|entity, _| query.get(*entity).is_ok());
+
+    // collect finished searches
+    let mut finished = Vec::new();
+    for (entity, task) in tasks.running.iter_mut() {
+        if let Some(result) = future::block_on(future::poll_once(task)) {
+            finished.push((*entity, result));
+        }
+    }
+    for (entity, (waypoints, elapsed)) in finished {
+        tasks.running.remove(&entity);
+        tasks.budget -= elapsed;
+        let mut entity_commands = commands.entity(entity);
+        match waypoints {
+            Some(waypoints) => {
+                entity_commands
+                    .insert(WaypointPath { waypoints })
+                    .insert(PathStatus::Found);
+                found_events.send(PathFound { entity });
+            }
+            None => {
+                entity_commands.insert(PathStatus::Failed);
+                failed_events.send(PathFailed { entity });
+            }
+        };
+        entity_commands.remove::<PathQuery>();
+    }
+
+    // start new searches, most important first
+    tasks.budget = (tasks.budget + tune::FRAME_BUDGET).min(tune::FRAME_BUDGET);
+    if tasks.queue.is_empty() || tasks.budget <= 0.0 {
+        return;
+    }
+    tasks
+        .queue
+        .sort_by_key(|request| (std::cmp::Reverse(request.priority), request.seq));
+    let costs = graph.costs();
+    let tile_size = resources.tile_size;
+    let pool = AsyncComputeTaskPool::get();
+    let num_started =
+        (tune::MAX_RUNNING.saturating_sub(tasks.running.len())).min(tasks.queue.len());
+    for request in tasks.queue.drain(..num_started) {
+        let costs = costs.clone();
+        let task = pool.spawn(async move {
+            let start = Instant::now();
+            let waypoints = plan_path(&costs, tile_size, request.start, request.end);
+            (waypoints, start.elapsed().as_secs_f32())
+        });
+        tasks.running.insert(request.entity, task);
+    }
+}
+
+// path between the hexes closest to start and end, see grid::find_path and smooth::string_pull. Ends inside a
+// wall are moved to the closest walkable hex that can be reached.
+fn plan_path(
+    costs: &HashMap<Cube, f32>,
+    tile_size: Vec2,
+    start: Vec3,
+    end: Vec3,
+) -> Option<Vec<Vec3>> {
+    let is_node = |cube| costs.contains_key(&cube);
+    let cost = |cube| costs.get(&cube).copied();
+    let start_cube = grid::nearest_node(
+        start.truncate(),
+        tile_size,
+        grid::tune::SNAP_RADIUS,
+        is_node,
+    )?;
+    let (path, _cost) =
+        grid::nodes_near(end.truncate(), tile_size, grid::tune::SNAP_RADIUS, is_node)
+            .into_iter()
+            .take(grid::tune::MAX_GOAL_CANDIDATES)
+            .find_map(|end_cube| grid::find_path(start_cube, end_cube, cost))?;
+    // all path finders are crab walkers
+    let waypoints = smooth::string_pull(&path, crate::tune::WALKER_RADIUS, tile_size, cost);
+    Some(waypoints.into_iter().map(|p| p.extend(0.0)).collect())
+}
+
+impl WaypointGraph {
+    // snapshot of the travel costs for the searches. Rebuilt after the graph changed.
+    fn costs(&mut self) -> Arc<HashMap<Cube, f32>> {
+        let nodes = &self.nodes;
+        self.costs
+            .get_or_insert_with(|| {
+                Arc::new(
+                    nodes
+                        .iter()
+                        .map(|(cube, node)| (*cube, node.cost))
+                        .collect(),
+                )
+            })
+            .clone()
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    hex::{
+        chunk::HexChunks,
+        io,
+        layer::HexTileLayers,
+        marker::MapMarker,
+        tilemap::{
+            spawn_tilemap, tilemap_snapshot, HexTileAppearance, HexTileCoord, HexTileIndex,
+            Resources,
+        },
+    },
+    item::medikit::Medikit,
+    movement::zap::Zappable,
+    Despawn, InputTarget, Pew,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    Editor,
+    Play,
+}
+
+// map as it was when leaving the editor. Restored when coming back, so playtesting does not leave traces in the
+// edited map.
+#[derive(Default)]
+pub struct PlaytestSnapshot {
+    pub tilemap: Option<io::Tilemap>,
+    // chunks that were not in the world at that time
+    pub chunk_cache: HashMap<IVec2, Vec<io::Tile>>,
+}
+
+fn toggle_state_system(keyboard_input: Res<Input<KeyCode>>, mut state: ResMut<State<AppState>>) {
+    if !keyboard_input.just_pressed(KeyCode::F1) {
+        return;
+    }
+    let next = match state.current() {
+        AppState::Editor => AppState::Play,
+        AppState::Play => AppState::Editor,
+    };
+    if let Err(err) = state.set(next) {
+        warn!("failed to switch to {:?}: {:?}", next, err);
+    }
+}
+
+fn snapshot_map_system(
+    mut playtest: ResMut<PlaytestSnapshot>,
+    chunks: Res<HexChunks>,
+    tile_query: Query<(&HexTileCoord, &HexTileAppearance, Option<&HexTileLayers>)>,
+    marker_query: Query<&MapMarker>,
+) {
+    playtest.tilemap = Some(tilemap_snapshot(tile_query.iter(), marker_query.iter()));
+    playtest.chunk_cache = chunks.cache.clone();
+}
+
+fn restore_map_system(
+    mut commands: Commands,
+    mut playtest: ResMut<PlaytestSnapshot>,
+    mut chunks: ResMut<HexChunks>,
+    mut index: ResMut<HexTileIndex>,
+    asset_server: Res<AssetServer>,
+    resources: Res<Resources>,
+    marker_query: Query<Entity, With<MapMarker>>,
+) {
+    let tilemap = match playtest.tilemap.take() {
+        Some(tilemap) => tilemap,
+        None => return,
+    };
+    // tiles and their waypoints go away with the chunks
+    chunks.despawn_all(&mut commands);
+    index.clear();
+    chunks.cache = std::mem::take(&mut playtest.chunk_cache);
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_tilemap(
+        &mut commands,
+        &asset_server,
+        &resources,
+        &mut chunks,
+        &mut index,
+        &tilemap,
+    );
+}
+
+// everything that only exists while playing (the spawn systems bring it back on the next playtest)
+#[allow(clippy::type_complexity)]
+fn cleanup_play_system(
+    mut commands: Commands,
+    query: Query<
+        Entity,
+        Or<(
+            With<InputTarget>,
+            With<Zappable>,
+            With<Pew>,
+            With<Medikit>,
+            With<Despawn>,
+        )>,
+    >,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(Despawn::ThisFrame);
+    }
+}
+
+pub struct AppStatePlugin;
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(AppState::Play)
+            .init_resource::<PlaytestSnapshot>()
+            .add_system(toggle_state_system)
+            .add_system_set(SystemSet::on_exit(AppState::Editor).with_system(snapshot_map_system))
+            .add_system_set(SystemSet::on_enter(AppState::Editor).with_system(restore_map_system))
+            .add_system_set(SystemSet::on_exit(AppState::Play).with_system(cleanup_play_system));
+    }
+}
@@ -10,6 +10,7 @@ pub struct TrackingOverlayTarget {
 }
 
 #[derive(Component)]
+#[allow(dead_code)]
 struct Link(Entity);
 
 fn add_tracking_overlays(